@@ -0,0 +1,30 @@
+#!/bin/bash
+# Lists the words in the public API of the Rust standard library that
+# spellck doesn't currently accept, as candidates for src/stdlib.txt.
+#
+# Usage: etc/stdlib-candidates.sh path/to/rust
+#
+# where path/to/rust is a checkout of rust-lang/rust, or the `rust-src`
+# component (`$(rustc --print sysroot)/lib/rustlib/src/rust`).
+
+set -o errexit -o nounset
+
+src=${1:?usage: $0 path/to/rust}
+if [ -n "${SPELLCK_STANDALONE:-}" ]; then
+    standalone=$SPELLCK_STANDALONE
+elif [ -x target/release/spellck_standalone ]; then
+    standalone=target/release/spellck_standalone
+else
+    standalone=target/debug/spellck_standalone
+fi
+
+for krate in "$src"/library/*/src/lib.rs; do
+    test -f "$krate" || continue
+    "$standalone" "$krate" || true
+done |
+    sed -n 's/^.*: misspelled words\{0,1\}: //p' |
+    sed 's/ (did you mean [^)]*)//g' |
+    tr ',' '\n' |
+    sed 's/^ *//' |
+    tr 'A-Z' 'a-z' |
+    sort -u
@@ -0,0 +1,276 @@
+#![crate_name = "cargo_spellck"]
+#![deny(missing_docs)]
+
+//! `cargo spellck`: prints the misspelled words in the public
+//! documentation & identifiers of every crate in a Cargo workspace.
+
+extern crate getopts;
+extern crate spellck;
+
+use std::collections::BTreeSet;
+use std::env;
+use std::io;
+use std::io::prelude::*;
+use std::path::PathBuf;
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use spellck::cargo::{self, Package, TargetKind, Workspace};
+use spellck::check::group_by_heading;
+use spellck::config::OutputFormat;
+use spellck::project::{self, Project};
+use spellck::source::{self, Crate};
+use spellck::{dict, report};
+
+/// The status to exit with once everything has been checked.
+static EXIT_STATUS: AtomicUsize = AtomicUsize::new(0);
+
+fn set_exit_status(code: usize) {
+    // an error loading something trumps finding misspellings.
+    if code > EXIT_STATUS.load(Ordering::SeqCst) {
+        EXIT_STATUS.store(code, Ordering::SeqCst)
+    }
+}
+
+fn main() {
+    run();
+    process::exit(EXIT_STATUS.load(Ordering::SeqCst) as i32)
+}
+
+fn run() {
+    let mut args = env::args().collect::<Vec<_>>();
+    // cargo runs `cargo-spellck spellck ...`.
+    if args.get(1).is_some_and(|a| a == "spellck") {
+        args.remove(1);
+    }
+
+    let mut opts = getopts::Options::new();
+    opts.optopt("", "manifest-path", "path to the `Cargo.toml` of the workspace or package",
+                "PATH");
+    opts.optmulti("p", "package", "only check this package", "NAME");
+    opts.optmulti("", "features", "space or comma separated list of features to enable",
+                  "FEATURES");
+    opts.optflag("", "all-features", "enable all features");
+    opts.optflag("", "no-default-features", "don't enable the `default` feature");
+    opts.optflag("", "lib", "check the libraries");
+    opts.optflag("", "bins", "check the binaries");
+    opts.optflag("", "examples", "check the examples");
+    opts.optflag("", "tests", "check the integration tests");
+    opts.optmulti("d", "dict",
+                  "dictionary file (a list of words, one per line, a \
+                   Hunspell `.dic` file or a compiled dictionary)",
+                  "PATH");
+    opts.optflag("n", "no-def-dict", "don't use the default dictionary");
+    opts.optflag("", "no-rust-dict", "don't use the built-in dictionary of Rust words");
+    opts.optopt("", "message-format",
+                "output format: `human` (the default) or `json`, which \
+                 can be read by `rustfix`", "FMT");
+    opts.optflag("h", "help", "show this help message");
+
+    let matches = match opts.parse(&args[1..]) {
+        Ok(m) => m,
+        Err(e) => {
+            let mut stderr = io::stderr();
+            (writeln!(&mut stderr, "{}", e)).unwrap();
+            set_exit_status(10);
+            return
+        }
+    };
+    if matches.opt_present("help") || !matches.free.is_empty() {
+        println!("{}", opts.usage("cargo spellck [options]\n\n\
+                                   Checks the lib, bin, example and test targets \
+                                   of each package in the workspace (unless some \
+                                   of `--lib`, `--bins`, `--examples` and `--tests` \
+                                   are given, in which case just those)."));
+        if !matches.opt_present("help") { set_exit_status(10) }
+        return;
+    }
+
+    // given on the command line, this overrides any configured format.
+    let format = match matches.opt_str("message-format") {
+        None => None,
+        Some(f) => match OutputFormat::from_name(&f) {
+            Some(f) => Some(f),
+            None => {
+                let mut stderr = io::stderr();
+                (writeln!(&mut stderr, "Unknown message format: {}", f)).unwrap();
+                set_exit_status(10);
+                return
+            }
+        }
+    };
+
+    let manifest = match matches.opt_str("manifest-path") {
+        Some(p) => Some(PathBuf::from(p)),
+        None => env::current_dir().ok().and_then(|d| cargo::find_manifest(&d)),
+    };
+    let manifest = match manifest {
+        Some(m) => m,
+        None => {
+            let mut stderr = io::stderr();
+            (writeln!(&mut stderr, "Error: could not find `Cargo.toml` in the current \
+                                    directory or any parent directory")).unwrap();
+            set_exit_status(10);
+            return
+        }
+    };
+    let workspace = match Workspace::load(&manifest) {
+        Ok(w) => w,
+        Err(e) => {
+            let mut stderr = io::stderr();
+            (writeln!(&mut stderr, "Error: {}", e)).unwrap();
+            set_exit_status(10);
+            return
+        }
+    };
+
+    let selected = matches.opt_strs("package");
+    for name in selected.iter() {
+        if !workspace.packages.iter().any(|p| p.name == *name) {
+            let mut stderr = io::stderr();
+            (writeln!(&mut stderr, "Error: package `{}` is not in the workspace",
+                      name)).unwrap();
+            set_exit_status(10);
+            return
+        }
+    }
+    let packages = workspace.packages.iter()
+        .filter(|p| selected.is_empty() || selected.contains(&p.name))
+        .collect::<Vec<_>>();
+
+    // `--features` can be given several times, with several features
+    // each time, which may be for a particular package, as
+    // `package/feature`.
+    let requested = matches.opt_strs("features").iter()
+        .flat_map(|f| f.split(|c: char| c == ',' || c.is_whitespace()))
+        .filter(|f| !f.is_empty())
+        .map(|f| f.to_string())
+        .collect::<Vec<_>>();
+    for f in requested.iter() {
+        let found = packages.iter().any(|p| {
+            match f.find('/') {
+                Some(i) => p.name == f[..i] && p.features.contains_key(&f[i + 1..]),
+                None => p.features.contains_key(f)
+            }
+        });
+        if !found {
+            let mut stderr = io::stderr();
+            (writeln!(&mut stderr, "Error: none of the selected packages has the \
+                                    feature `{}`", f)).unwrap();
+            set_exit_status(10);
+            return
+        }
+    }
+
+    let kinds = [(TargetKind::Lib, "lib"), (TargetKind::Bin, "bins"),
+                 (TargetKind::Example, "examples"), (TargetKind::Test, "tests")];
+    let mut checked = kinds.iter()
+        .filter(|&&(_, flag)| matches.opt_present(flag))
+        .map(|&(kind, _)| kind)
+        .collect::<Vec<_>>();
+    if checked.is_empty() {
+        checked = kinds.iter().map(|&(kind, _)| kind).collect();
+    }
+
+    let dicts = matches.opt_strs("d");
+    let words = match project::base_words(!matches.opt_present("no-rust-dict"),
+                                          !matches.opt_present("no-def-dict"),
+                                          &dicts) {
+        Ok(w) => w,
+        Err(e) => {
+            let mut stderr = io::stderr();
+            (writeln!(&mut stderr, "Error: {}", e)).unwrap();
+            set_exit_status(10);
+            return
+        }
+    };
+
+    let mut total = 0;
+    for package in packages {
+        let features = package.enabled_features(&features_for(package, &requested),
+                                                matches.opt_present("all-features"),
+                                                !matches.opt_present("no-default-features"));
+        let count = check_package(package, &features, &checked, &words, format);
+        total += count;
+    }
+    if total > 0 {
+        set_exit_status(1)
+    }
+}
+
+/// The requested features that apply to `package`, without any
+/// `package/` prefix.
+fn features_for(package: &Package, requested: &[String]) -> Vec<String> {
+    requested.iter().filter_map(|f| {
+        match f.find('/') {
+            Some(i) if f[..i] == package.name => Some(f[i + 1..].to_string()),
+            Some(_) => None,
+            None => Some(f.clone()),
+        }
+    }).collect()
+}
+
+/// Check the targets of `package` of the kinds in `kinds`, printing
+/// what is found and returning the number of misspellings.
+fn check_package(package: &Package, features: &BTreeSet<String>,
+                 kinds: &[TargetKind], words: &dict::Chain,
+                 format: Option<OutputFormat>) -> usize {
+    let mut stderr = io::stderr();
+    (writeln!(&mut stderr, "    Checking {} v{} ({})",
+              package.name, package.version, package.root.display())).unwrap();
+
+    let mut count = 0;
+    for target in package.targets.iter().filter(|t| kinds.contains(&t.kind)) {
+        if !target.required_features.iter().all(|f| features.contains(f)) {
+            continue
+        }
+
+        let project = match Project::load(&target.path) {
+            Ok(p) => p,
+            Err(e) => {
+                (writeln!(&mut stderr, "Error: {}", e)).unwrap();
+                set_exit_status(10);
+                continue
+            }
+        };
+        let json = format.or(project.config.output_format) == Some(OutputFormat::Json);
+
+        let cfg = package.cfg(target, features);
+        let krate = match Crate::load_with_cfg(&target.path, &cfg) {
+            Ok(k) => k,
+            Err(e) => {
+                (writeln!(&mut stderr, "Error loading {} `{}`: {}",
+                          target.kind.name(), target.name, e)).unwrap();
+                set_exit_status(10);
+                continue
+            }
+        };
+
+        let mut crate_words = dict::Chain::new();
+        crate_words.push(words);
+        crate_words.push(&project.words);
+        crate_words.push(krate.extra_words());
+        let dict = &crate_words;
+
+        let mut checker = project.checker(&crate_words);
+        let misspellings = source::check_crate(&mut checker, &krate);
+        let cm = &krate.source_map;
+        for (&sp, words) in misspellings.iter() {
+            count += words.len();
+            for (heading, group) in group_by_heading(words) {
+                let message = report::message(heading, &group, dict);
+                if json {
+                    println!("{}", report::json(cm, sp, &message, &group, dict));
+                } else {
+                    print!("{}", report::human(cm, sp, &message, &group));
+                }
+            }
+        }
+    }
+
+    if count > 0 {
+        (writeln!(&mut stderr, "warning: `{}` has {} misspelling{}",
+                  package.name, count, if count == 1 { "" } else { "s" })).unwrap();
+    }
+    count
+}
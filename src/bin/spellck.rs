@@ -0,0 +1,127 @@
+#![deny(missing_docs)]
+
+//! Utilities for working with spellck's dictionaries, and for
+//! running it in an editor.
+//!
+//! - `spellck dict compile -o OUT INPUT...`: merge the dictionaries
+//!   `INPUT...` (in any format) into a single precompiled dictionary,
+//!   which loads much faster than a list of words.
+//! - `spellck lsp`: run a language server on stdin and stdout (see
+//!   `spellck::lsp`).
+
+extern crate getopts;
+
+extern crate spellck;
+
+use std::env;
+use std::fs::File;
+use std::io;
+use std::io::prelude::*;
+use std::process;
+
+use spellck::{dict, compiled, lsp, project};
+
+fn main() {
+    let args = env::args().collect::<Vec<_>>();
+    let cmd = args.iter().skip(1).take(2).map(|s| &**s).collect::<Vec<_>>();
+
+    if cmd == ["dict", "compile"] {
+        dict_compile(&args[0], &args[3..])
+    } else if cmd.first() == Some(&"lsp") {
+        lsp_server(&args[0], &args[2..])
+    } else {
+        let mut stderr = io::stderr();
+        (writeln!(&mut stderr, "Usage: {0} dict compile -o OUT INPUT...\n       {0} lsp [options]",
+                  args[0])).unwrap();
+        process::exit(10)
+    }
+}
+
+fn lsp_server(prog: &str, args: &[String]) {
+    let mut opts = getopts::Options::new();
+    opts.optmulti("d", "dict",
+                  "dictionary file (a list of words, one per line, a \
+                   Hunspell `.dic` file or a compiled dictionary)",
+                  "PATH");
+    opts.optflag("n", "no-def-dict", "don't use the default dictionary");
+    opts.optflag("", "no-rust-dict", "don't use the built-in dictionary of Rust words");
+    opts.optflag("h", "help", "show this help message");
+    let usage = format!("{} lsp [options]", prog);
+
+    let matches = match opts.parse(args) {
+        Ok(m) => m,
+        Err(e) => {
+            let mut stderr = io::stderr();
+            (writeln!(&mut stderr, "{}\n{}", e, opts.usage(&usage))).unwrap();
+            process::exit(10)
+        }
+    };
+    if matches.opt_present("help") {
+        println!("{}", opts.usage(&usage));
+        return
+    }
+
+    let words = match project::base_words(!matches.opt_present("no-rust-dict"),
+                                          !matches.opt_present("no-def-dict"),
+                                          &matches.opt_strs("dict")) {
+        Ok(w) => w,
+        Err(e) => {
+            let mut stderr = io::stderr();
+            (writeln!(&mut stderr, "Error: {}", e)).unwrap();
+            process::exit(10)
+        }
+    };
+    if let Err(e) = lsp::run(words) {
+        let mut stderr = io::stderr();
+        (writeln!(&mut stderr, "Error: {}", e)).unwrap();
+        process::exit(10)
+    }
+}
+
+fn dict_compile(prog: &str, args: &[String]) {
+    let mut opts = getopts::Options::new();
+    opts.optopt("o", "output", "the file to write the dictionary to", "PATH");
+    opts.optflag("h", "help", "show this help message");
+    let usage = format!("{} dict compile -o OUT INPUT...", prog);
+
+    let matches = match opts.parse(args) {
+        Ok(m) => m,
+        Err(e) => {
+            let mut stderr = io::stderr();
+            (writeln!(&mut stderr, "{}\n{}", e, opts.usage(&usage))).unwrap();
+            process::exit(10)
+        }
+    };
+    if matches.opt_present("help") {
+        println!("{}", opts.usage(&usage));
+        return
+    }
+
+    let out = match matches.opt_str("output") {
+        Some(o) => o,
+        None => {
+            let mut stderr = io::stderr();
+            (writeln!(&mut stderr, "no output file given\n{}", opts.usage(&usage))).unwrap();
+            process::exit(10)
+        }
+    };
+
+    let mut words = vec![];
+    for input in matches.free.iter() {
+        match dict::load(input) {
+            Ok(d) => d.each_entry(&mut |w| words.push(w.to_string())),
+            Err(e) => {
+                let mut stderr = io::stderr();
+                (writeln!(&mut stderr, "Error reading {}: {}", input, e)).unwrap();
+                process::exit(10)
+            }
+        }
+    }
+
+    let bytes = compiled::compile(words);
+    if let Err(e) = File::create(&out).and_then(|mut f| f.write_all(&bytes)) {
+        let mut stderr = io::stderr();
+        (writeln!(&mut stderr, "Error writing {}: {}", out, e)).unwrap();
+        process::exit(10)
+    }
+}
@@ -1,226 +1,415 @@
 #![crate_name = "spellck_standalone"]
 #![deny(missing_docs)]
-#![feature(rustc_private)]
-#![feature(collections, exit_status)]
 
 //! Prints the misspelled words in the public documentation &
 //! identifiers of a crate.
 
 extern crate getopts;
-extern crate arena;
-extern crate syntax;
-extern crate rustc;
-extern crate rustc_driver;
-extern crate rustc_trans;
-
-#[allow(plugin_as_library)]
 extern crate spellck;
 
 use std::env;
-use std::path::{Path, PathBuf};
-use std::fs::File;
+use std::path::Path;
+use std::fs::{File, OpenOptions};
 use std::io;
 use std::io::prelude::*;
-use std::cell::Cell;
-use std::cmp::Ordering;
-use std::collections::{HashMap, HashSet, BinaryHeap};
-use syntax::ast;
-use syntax::codemap::{Span, BytePos};
-use syntax::diagnostics;
-use rustc::middle::{privacy, ty};
-use rustc::session::{self, config};
-use rustc_driver::{driver, pretty, Compilation};
+use std::iter::repeat_n;
+use std::cell::{Cell, RefCell};
+use std::cmp::Reverse;
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::collections::{HashMap, HashSet};
+
+use spellck::check::{Span, Misspelling, Kind, group_by_heading};
+use spellck::cfg::Cfg;
+use spellck::source::{self, Crate, SourceMap};
+use spellck::project::{self, Project};
+use spellck::{suggest, dict, report};
+use spellck::dict::Dictionary;
+use spellck::config::OutputFormat;
+use spellck::wordmap;
 
-use spellck::visitor::SpellingVisitor;
+/// The status to exit with once everything has been checked.
+static EXIT_STATUS: AtomicUsize = AtomicUsize::new(0);
 
-static DEFAULT_DICT: &'static str = "/usr/share/dict/words";
-static LIBDIR: &'static str = "/usr/local/lib/rustlib/x86_64-unknown-linux-gnu/lib";
+fn set_exit_status(code: usize) {
+    EXIT_STATUS.store(code, Ordering::SeqCst)
+}
 
 fn main() {
+    run();
+    process::exit(EXIT_STATUS.load(Ordering::SeqCst) as i32)
+}
+
+fn run() {
     let args = env::args().collect::<Vec<_>>();
-    let opts = &[getopts::optmulti("d", "dict",
-                                  "dictionary file (a list of words, one per line)", "PATH"),
-                getopts::optflag("n", "no-def-dict", "don't use the default dictionary"),
-                getopts::optflag("h", "help", "show this help message")];
+    let mut opts = getopts::Options::new();
+    opts.optmulti("d", "dict",
+                  "dictionary file (a list of words, one per line, a \
+                   Hunspell `.dic` file or a compiled dictionary)",
+                  "PATH");
+    opts.optflag("n", "no-def-dict", "don't use the default dictionary");
+    opts.optflag("", "no-rust-dict", "don't use the built-in dictionary of Rust words");
+    opts.optmulti("", "cfg",
+                  "only check the items enabled with this configuration option, \
+                   like `unix` or `feature=\"foo\"` (by default, everything is \
+                   checked)", "SPEC");
+    opts.optopt("", "message-format",
+                "output format: `human` (the default) or `json`, which \
+                 can be read by `rustfix`", "FMT");
+    opts.optflag("i", "interactive",
+                 "step through each misspelling, choosing how to correct it");
+    opts.optflag("h", "help", "show this help message");
 
-    let matches = getopts::getopts(args.tail(), opts).unwrap();
+    let matches = match opts.parse(&args[1..]) {
+        Ok(m) => m,
+        Err(e) => {
+            let mut stderr = io::stderr();
+            (writeln!(&mut stderr, "{}", e)).unwrap();
+            set_exit_status(10);
+            return
+        }
+    };
     if matches.opt_present("help") {
-        println!("{}", getopts::usage(&args[0], opts));
+        println!("{}", opts.usage(&format!("{} [options] CRATE_ROOT...", args[0])));
         return;
     }
 
-    let mut words = HashSet::new();
+    // given on the command line, this overrides any configured format.
+    let format = match matches.opt_str("message-format") {
+        None => None,
+        Some(f) => match OutputFormat::from_name(&f) {
+            Some(f) => Some(f),
+            None => {
+                let mut stderr = io::stderr();
+                (write!(&mut stderr, "Unknown message format: {}", f)).unwrap();
+                set_exit_status(10);
+                return
+            }
+        }
+    };
 
-    if !matches.opt_present("no-def-dict") {
-        if !read_lines_into(DEFAULT_DICT, &mut words) {
+    let dicts = matches.opt_strs("d");
+    let interactive = if matches.opt_present("interactive") {
+        if format == Some(OutputFormat::Json) {
+            let mut stderr = io::stderr();
+            (write!(&mut stderr, "--interactive cannot be used with JSON output")).unwrap();
+            set_exit_status(10);
             return
         }
-    }
-    for dict in matches.opt_strs("d").into_iter().chain(matches.opt_strs("dict").into_iter()) {
-        if !read_lines_into(&dict, &mut words) {
+        Some(Interactive::new(dicts.clone()))
+    } else {
+        None
+    };
+
+    let words = match project::base_words(!matches.opt_present("no-rust-dict"),
+                                          !matches.opt_present("no-def-dict"),
+                                          &dicts) {
+        Ok(w) => w,
+        Err(e) => {
+            let mut stderr = io::stderr();
+            (writeln!(&mut stderr, "Error: {}", e)).unwrap();
+            set_exit_status(10);
             return
         }
-    }
-
-    // one visitor; the internal list of misspelled words gets reset
-    // for each file, since the spans could conflict.
-    let any_mistakes = Cell::new(false);
+    };
 
-    for name in matches.free {
-        get_ast(name,
-                |sess, krate, export, _public| {
-            let cm = sess.codemap();
+    // only evaluate `#[cfg]`s if asked to, since otherwise there's no
+    // way to know which features are meant to be enabled.
+    let specs = matches.opt_strs("cfg");
+    let cfg = if specs.is_empty() {
+        None
+    } else {
+        let mut cfg = Cfg::host();
+        for spec in specs.iter() {
+            cfg.set_spec(spec);
+        }
+        Some(cfg)
+    };
 
-            let mut visitor = SpellingVisitor::new(&words, export);
-            visitor.check_crate(krate);
+    let mut any_mistakes = false;
 
-            struct Sort<'a> {
-                sp: Span,
-                words: &'a Vec<String>
-            }
-            impl<'a> PartialEq for Sort<'a> {
-                fn eq(&self, other: &Sort<'a>) -> bool {
-                    self.sp == other.sp
-                }
-            }
-            impl<'a> PartialOrd for Sort<'a> {
-                fn partial_cmp(&self, other: &Sort<'a>) -> Option<Ordering> {
-                    Some(self.cmp(other))
-                }
+    for name in matches.free {
+        let project = match Project::load(Path::new(&name)) {
+            Ok(p) => p,
+            Err(e) => {
+                let mut stderr = io::stderr();
+                (writeln!(&mut stderr, "Error: {}", e)).unwrap();
+                set_exit_status(10);
+                return
             }
-            impl<'a> Eq for Sort<'a> {}
-            impl<'a> Ord for Sort<'a> {
-                fn cmp(&self, other: &Sort<'a>) -> Ordering {
-                    let Span { lo: BytePos(slo), hi: BytePos(shi), .. } = self.sp;
-                    let Span { lo: BytePos(olo), hi: BytePos(ohi), .. } = other.sp;
-                    (slo, shi).cmp(&(olo, ohi))
-                }
+        };
+        // the interactive mode has its own output.
+        let json = interactive.is_none() &&
+            format.or(project.config.output_format) == Some(OutputFormat::Json);
+
+        let krate = match cfg {
+            Some(ref cfg) => Crate::load_with_cfg(Path::new(&name), cfg),
+            None => Crate::load(Path::new(&name)),
+        };
+        let krate = match krate {
+            Ok(k) => k,
+            Err(e) => {
+                let mut stderr = io::stderr();
+                (writeln!(&mut stderr, "Error loading crate: {}", e)).unwrap();
+                set_exit_status(10);
+                return
             }
+        };
 
-            // extract the lines in order of the spans, so that e.g. files
-            // are grouped together, and lines occur in increasing order.
-            let pq: BinaryHeap<Sort> =
-                visitor.misspellings.iter().map(|(pos, v)| Sort { sp: pos.span, words: v }).collect();
+        // the words of the project this crate is part of.
+        let mut crate_words = dict::Chain::new();
+        crate_words.push(&words);
+        crate_words.push(&project.words);
+        crate_words.push(krate.extra_words());
 
-            // run through the spans, printing the words that are
-            // apparently misspelled
-            for Sort {sp, words} in pq.into_sorted_vec().into_iter() {
-                any_mistakes.set(true);
+        let cm = &krate.source_map;
 
-                let lines = cm.span_to_lines(sp);
-                let sp_text = cm.span_to_string(sp);
+        let mut checker = project.checker(&crate_words);
+        let misspellings = source::check_crate(&mut checker, &krate);
 
-                // [] required for connect :(
-                let word_vec: Vec<&str> = words.iter().map(|s| &**s).collect();
+        // run through the spans (which are in order, so e.g. files
+        // are grouped together, and lines occur in increasing order),
+        // printing the words that are apparently misspelled
+        for (&sp, words) in misspellings.iter() {
+            if let Some(ref session) = interactive {
+                for m in words.iter() {
+                    if !session.correct(cm, sp, m, &crate_words) {
+                        any_mistakes = true
+                    }
+                }
+                continue
+            }
 
-                println!("{}: misspelled {words}: {}",
-                         sp_text,
-                         word_vec.connect(", "),
-                         words = if words.len() == 1 {"word"} else {"words"});
+            any_mistakes = true;
 
-                // first line; no lines = no printing
-                if lines.lines.len() >= 1 {
-                    let line_num = lines.lines[0].line_index;
-                    if let Some(line) = lines.file.get_line(line_num) {
-                        println!("{}: {}", sp_text, line);
-                    }
+            for (heading, group) in group_by_heading(words) {
+                let message = report::message(heading, &group, &crate_words);
+                if json {
+                    println!("{}", report::json(cm, sp, &message, &group, &crate_words));
+                } else {
+                    print!("{}", report::human(cm, sp, &message, &group));
                 }
             }
-        })
+        }
     }
 
-    if any_mistakes.get() {
-        env::set_exit_status(1)
+    if let Some(session) = interactive {
+        if !session.apply_edits() {
+            set_exit_status(10);
+            return
+        }
     }
-}
 
-/// Load each line of the file `p` into the given `Extend` object.
-fn read_lines_into<P: AsRef<Path> + ::std::fmt::Debug + ?Sized, E: Extend<String>>
-                  (p: &P, e: &mut E) -> bool {
-    match File::open(p) {
-        Ok(mut r) => {
-            let mut s = String::new();
-            r.read_to_string(&mut s).unwrap();
-            e.extend(s.lines().map(|ss| ss.to_string()));
-            true
-        }
-        Err(e) => {
-            let mut stderr = io::stderr();
-            (write!(&mut stderr,
-                    "Error reading {:?}: {}", p, e)).unwrap();
-            env::set_exit_status(10);
-            false
-        }
+    if any_mistakes {
+        set_exit_status(1)
     }
 }
 
-type Externs = HashMap<String, Vec<String>>;
+/// An edit to a source file chosen in `--interactive` mode.
+struct Edit {
+    file: String,
+    lo: usize,
+    hi: usize,
+    original: String,
+    replacement: String,
+}
 
-struct Calls<F> {
-    f: Option<F>
+/// The state of an `--interactive` session, which persists across
+/// all the crates being checked.
+struct Interactive {
+    /// The dictionaries given with `-d`, which words can be added to.
+    dicts: Vec<String>,
+    /// Words to skip for the rest of the session, either because they
+    /// were ignored everywhere or added to a dictionary.
+    ignored: RefCell<HashSet<String>>,
+    /// The replacements to write back to the source once all crates
+    /// have been checked.
+    edits: RefCell<Vec<Edit>>,
+    quit: Cell<bool>,
 }
 
-impl<'a, F> rustc_driver::CompilerCalls<'a> for Calls<F>
-    where F: 'a + Fn(&session::Session, &ast::Crate,
-                     &privacy::ExportedItems, &privacy::PublicItems)
-{
-    fn early_callback(&mut self,
-                      _matches: &getopts::Matches,
-                      _descriptions: &diagnostics::registry::Registry)
-                      -> Compilation {
-        Compilation::Continue
+impl Interactive {
+    fn new(dicts: Vec<String>) -> Interactive {
+        Interactive {
+            dicts,
+            ignored: RefCell::new(HashSet::new()),
+            edits: RefCell::new(vec![]),
+            quit: Cell::new(false),
+        }
     }
 
-    fn no_input(&mut self,
-                _matches: &getopts::Matches,
-                _sopts: &config::Options,
-                _odir: &Option<PathBuf>,
-                _ofile: &Option<PathBuf>,
-                _descriptions: &diagnostics::registry::Registry)
-                -> Option<(config::Input, Option<PathBuf>)> {
-        unreachable!()
+    /// Show a misspelling to the user and ask what to do about it,
+    /// returning `false` if it was left as is.
+    fn correct(&self, cm: &SourceMap, sp: Span, m: &Misspelling,
+               dict: &dyn Dictionary) -> bool {
+        if self.quit.get() { return false }
+        if self.ignored.borrow().contains(&m.word) { return true }
+
+        let word_sp = m.span.unwrap_or(sp);
+        let loc = cm.lookup_char_pos(word_sp.lo);
+        println!("{}: {}: {}", cm.span_to_string(word_sp), m.kind.heading(), m.word);
+        if let Some(line) = loc.file.get_line(loc.line - 1) {
+            println!("{}", line);
+            if m.span.is_some() {
+                let marker = repeat_n(' ', loc.col)
+                    .chain(repeat_n('^', m.word.chars().count()))
+                    .collect::<String>();
+                println!("{}", marker);
+            }
+        }
+
+        let suggestions = match m.kind {
+            Kind::Capitalization(ref c) => vec![c.clone()],
+            Kind::KnownMisspelling(ref r) => {
+                wordmap::alternatives(r).iter().map(|a| a.to_string()).collect()
+            }
+            _ => suggest::suggestions(&m.word, dict, 10)
+        };
+        // identifiers are used elsewhere in the crate (and in other
+        // crates), so renaming them is left to the user.
+        let editable = m.span.is_some();
+        if editable && !suggestions.is_empty() {
+            let listed = suggestions.iter().enumerate()
+                .map(|(i, s)| format!("{}) {}", i, s)).collect::<Vec<_>>();
+            println!("    {}", listed.join("  "));
+        }
+
+        loop {
+            if editable {
+                print!("[0-9] replace, r) replace with..., ");
+            }
+            print!("i) ignore, I) ignore all, a) add to dictionary, q) quit: ");
+            io::stdout().flush().unwrap();
+
+            let answer = match read_answer() {
+                Some(a) => a,
+                None => { self.quit.set(true); return false }
+            };
+            match &*answer {
+                "i" => return false,
+                "I" => {
+                    self.ignored.borrow_mut().insert(m.word.clone());
+                    return true
+                }
+                "a" => if self.add_to_dictionary(&m.word) { return true },
+                "q" => { self.quit.set(true); return false }
+                "r" if editable => {
+                    print!("replace `{}` with: ", m.word);
+                    io::stdout().flush().unwrap();
+                    match read_answer() {
+                        Some(ref r) if !r.is_empty() => {
+                            self.replace(cm, word_sp, m, r.clone());
+                            return true
+                        }
+                        _ => {}
+                    }
+                }
+                n => match n.parse::<usize>().ok().and_then(|i| suggestions.get(i)) {
+                    Some(r) if editable => {
+                        self.replace(cm, word_sp, m, r.clone());
+                        return true
+                    }
+                    _ => println!("unrecognised choice `{}`", n)
+                }
+            }
+        }
     }
 
-    fn parse_pretty(&mut self,
-                    _sess: &session::Session,
-                    _matches: &getopts::Matches)
-                    -> Option<(pretty::PpMode, Option<pretty::UserIdentifiedItem>)> {
-        None
+    /// Append `word` to one of the dictionaries, asking which if
+    /// there are several. Returns `false` if nothing was added.
+    fn add_to_dictionary(&self, word: &str) -> bool {
+        let path = match self.dicts.len() {
+            0 => {
+                println!("no dictionary to add to: use `-d` to specify one");
+                return false
+            }
+            1 => &self.dicts[0],
+            _ => {
+                for (i, d) in self.dicts.iter().enumerate() {
+                    println!("    {}) {}", i, d);
+                }
+                print!("add `{}` to which dictionary? ", word);
+                io::stdout().flush().unwrap();
+                match read_answer().and_then(|a| a.parse::<usize>().ok())
+                                   .and_then(|i| self.dicts.get(i)) {
+                    Some(d) => d,
+                    None => return false
+                }
+            }
+        };
+
+        let res = OpenOptions::new().append(true).open(path).and_then(|mut f| {
+            writeln!(&mut f, "{}", word)
+        });
+        match res {
+            Ok(()) => {
+                self.ignored.borrow_mut().insert(word.to_string());
+                true
+            }
+            Err(e) => {
+                println!("error writing to {}: {}", path, e);
+                false
+            }
+        }
     }
 
-    fn late_callback(&mut self,
-                     matches: &getopts::Matches,
-                     sess: &session::Session,
-                     input: &config::Input,
-                     odir: &Option<PathBuf>,
-                     ofile: &Option<PathBuf>)
-                     -> Compilation {
-        rustc_driver::RustcDefaultCalls.late_callback(matches, sess, input, odir, ofile)
+    fn replace(&self, cm: &SourceMap, sp: Span, m: &Misspelling, replacement: String) {
+        let (file, lo) = cm.lookup_byte_offset(sp.lo);
+        self.edits.borrow_mut().push(Edit {
+            file: file.name.clone(),
+            lo,
+            hi: lo + (sp.hi - sp.lo),
+            original: m.word.clone(),
+            replacement,
+        })
     }
 
-    fn build_controller(&mut self, _sess: &session::Session) -> driver::CompileController<'a> {
-        let f = self.f.take().unwrap();
-        let mut controller = driver::CompileController::basic();
-        controller.after_analysis = driver::PhaseController {
-            stop: rustc_driver::Compilation::Stop,
-            callback: Box::new(move |state| {
-                let ca = state.analysis.unwrap();
-                let ty::CrateAnalysis { ref exported_items, ref public_items, ref ty_cx, .. } = *ca;
-                f(&ty_cx.sess, ty_cx.map.krate(), exported_items, public_items)
-            })
-        };
-        controller
+    /// Write all the chosen replacements back to the source files,
+    /// returning `false` if any couldn't be.
+    fn apply_edits(&self) -> bool {
+        let all_edits = self.edits.borrow();
+        let mut by_file = HashMap::new();
+        for e in all_edits.iter() {
+            by_file.entry(&*e.file).or_insert(vec![]).push(e);
+        }
+
+        let mut ok = true;
+        for (file, mut edits) in by_file.into_iter() {
+            let mut contents = String::new();
+            if let Err(e) = File::open(file).and_then(|mut f| f.read_to_string(&mut contents)) {
+                println!("error reading {}: {}", file, e);
+                ok = false;
+                continue
+            }
+
+            // back to front, so earlier offsets remain valid.
+            edits.sort_by_key(|e| Reverse(e.lo));
+            for e in edits.iter() {
+                if e.hi > contents.len() ||
+                        contents.as_bytes()[e.lo..e.hi] != *e.original.as_bytes() {
+                    println!("{}: `{}` has changed since it was checked, not replacing it",
+                             file, e.original);
+                    ok = false;
+                    continue
+                }
+                contents = format!("{}{}{}", &contents[..e.lo], e.replacement, &contents[e.hi..]);
+            }
+
+            if let Err(e) = File::create(file).and_then(|mut f| f.write_all(contents.as_bytes())) {
+                println!("error writing {}: {}", file, e);
+                ok = false;
+            }
+        }
+        ok
     }
 }
 
-/// Extract the expanded ast of a krate, along with the codemap which
-/// connects source code locations to the actual code.
-#[allow(deprecated)]
-fn get_ast<F>(path: String,
-              f: F)
-        where F: Fn(&session::Session, &ast::Crate,
-                    &privacy::ExportedItems, &privacy::PublicItems)
-{
-    let mut calls = Calls { f: Some(f) };
-    rustc_driver::run_compiler(&[format!("-L{}", LIBDIR), path], &mut calls);
+/// Read a line of input from the user, without the trailing newline;
+/// `None` at the end of the input.
+fn read_answer() -> Option<String> {
+    let mut s = String::new();
+    match io::stdin().read_line(&mut s) {
+        Ok(0) | Err(_) => None,
+        Ok(_) => Some(s.trim().to_string())
+    }
 }
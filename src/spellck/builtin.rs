@@ -0,0 +1,73 @@
+//! Dictionaries built into spellck.
+
+use std::collections::HashSet;
+
+use dict;
+use wordmap::WordMap;
+
+/// Words, abbreviations and sequences of letters from the public API
+/// of the Rust standard library that are correct, but aren't in a
+/// normal English dictionary (`libc`, `hashmap`, `utf8`, ...). This is
+/// the contents of `src/stdlib.txt`.
+pub static RUST_WORDS: &str = include_str!("../stdlib.txt");
+
+/// The Rust vocabulary, `RUST_WORDS`, as a dictionary.
+pub fn rust() -> HashSet<String> {
+    word_set(RUST_WORDS)
+}
+
+/// Acronyms that are correct, in capitals (`HTTP`, `IO`, ...). These
+/// are only used for words that are a run of capitals, so that
+/// e.g. `Io` isn't accepted just because `IO` is. This is the contents
+/// of `src/acronyms.txt`.
+pub static ACRONYMS: &str = include_str!("../acronyms.txt");
+
+/// The acronyms, `ACRONYMS`, as a dictionary.
+pub fn acronyms() -> HashSet<String> {
+    word_set(ACRONYMS)
+}
+
+/// Common misspellings and their corrections (`recieve->receive`), in
+/// the format of `wordmap`. These are never correct, so they're
+/// reported even when a dictionary (which may well have been
+/// collected from misspelled text) contains them. This is the contents
+/// of `src/misspellings.txt`.
+pub static MISSPELLINGS: &str = include_str!("../misspellings.txt");
+
+/// The known misspellings, `MISSPELLINGS`, as a map to their
+/// corrections.
+pub fn misspellings() -> WordMap {
+    let mut map = WordMap::new();
+    map.parse(MISSPELLINGS);
+    map
+}
+
+/// A list of general English words, bundled so that spellck works the
+/// same everywhere, including machines without a system word list
+/// (e.g. minimal containers). This is the contents of
+/// `src/english.txt`, the en_US Hunspell dictionary made from SCOWL,
+/// with its affixes expanded (see `LICENSE-SCOWL`).
+#[cfg(feature = "bundled-dict")]
+pub static ENGLISH_WORDS: &str = include_str!("../english.txt");
+
+/// The bundled English dictionary, `ENGLISH_WORDS`, or `None` if
+/// spellck was built without the `bundled-dict` feature.
+#[cfg(feature = "bundled-dict")]
+pub fn english() -> Option<HashSet<String>> {
+    Some(word_set(ENGLISH_WORDS))
+}
+
+/// The bundled English dictionary, `ENGLISH_WORDS`, or `None` if
+/// spellck was built without the `bundled-dict` feature.
+#[cfg(not(feature = "bundled-dict"))]
+pub fn english() -> Option<HashSet<String>> {
+    None
+}
+
+fn word_set(words: &str) -> HashSet<String> {
+    words.lines()
+        .map(|l| l.trim())
+        .filter(|l| !l.is_empty())
+        .map(dict::normalize)
+        .collect()
+}
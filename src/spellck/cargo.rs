@@ -0,0 +1,413 @@
+//! Finding the crates of a Cargo workspace from its manifests, without
+//! running Cargo itself (which may need the network to resolve
+//! dependencies, or a lock file that doesn't exist yet).
+//!
+//! The members of a workspace and their targets are found the way
+//! Cargo finds them: the `[workspace]` `members` (which may be globs)
+//! and `exclude`, and each package's `[lib]`, `[[bin]]`, `[[example]]`
+//! and `[[test]]` sections, along with the targets discovered
+//! automatically from `src/lib.rs`, `src/main.rs`, `src/bin`,
+//! `examples` and `tests`.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use toml;
+
+use cfg::Cfg;
+use config::{self, glob_matches, strings};
+
+/// The kinds of targets that are checked.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum TargetKind {
+    Lib,
+    Bin,
+    Example,
+    Test,
+}
+
+impl TargetKind {
+    /// The name of this kind, as in `[[bin]]`.
+    pub fn name(self) -> &'static str {
+        match self {
+            TargetKind::Lib => "lib",
+            TargetKind::Bin => "bin",
+            TargetKind::Example => "example",
+            TargetKind::Test => "test",
+        }
+    }
+
+    /// The directory targets of this kind are discovered in, and the
+    /// key that turns that off.
+    fn auto_dir(self) -> Option<(&'static str, &'static str)> {
+        match self {
+            TargetKind::Lib => None,
+            TargetKind::Bin => Some(("src/bin", "autobins")),
+            TargetKind::Example => Some(("examples", "autoexamples")),
+            TargetKind::Test => Some(("tests", "autotests")),
+        }
+    }
+}
+
+/// A crate built by a package.
+#[derive(Clone, Debug)]
+pub struct Target {
+    pub kind: TargetKind,
+    pub name: String,
+    /// The root file of the crate.
+    pub path: PathBuf,
+    /// The features that must be enabled for the target to be built.
+    pub required_features: Vec<String>,
+}
+
+/// A package, as described by its `Cargo.toml`.
+#[derive(Clone, Debug)]
+pub struct Package {
+    pub name: String,
+    pub version: String,
+    /// The directory containing the manifest.
+    pub root: PathBuf,
+    /// The `[features]` table, including the implicit feature of each
+    /// optional dependency.
+    pub features: BTreeMap<String, Vec<String>>,
+    pub targets: Vec<Target>,
+}
+
+impl Package {
+    /// Read the package with manifest `manifest`.
+    pub fn load(manifest: &Path) -> Result<Package, String> {
+        let value = config::read_toml(manifest)?;
+        let root = manifest.parent().unwrap_or(Path::new(".")).to_path_buf();
+        Package::from_toml(&value, &root).map_err(|e| format!("{}: {}", manifest.display(), e))
+    }
+
+    /// Read the package from the parsed manifest `value`, which is
+    /// in directory `root`.
+    pub fn from_toml(value: &toml::Value, root: &Path) -> Result<Package, String> {
+        let package = match value.get("package") {
+            Some(p) => p,
+            None => return Err("no `[package]` section".to_string())
+        };
+        let name = match package.get("name").and_then(|n| n.as_str()) {
+            Some(n) => n.to_string(),
+            None => return Err("no package name".to_string())
+        };
+        // `version.workspace = true` and the like are only shown, so
+        // aren't worth chasing.
+        let version = package.get("version").and_then(|v| v.as_str()).unwrap_or("0.0.0");
+
+        let mut features = BTreeMap::new();
+        if let Some(table) = value.get("features").and_then(|f| f.as_table()) {
+            for (feature, enables) in table.iter() {
+                features.insert(feature.clone(), strings(feature, enables)?);
+            }
+        }
+        // an optional dependency is a feature of the same name, unless
+        // something refers to it as `dep:name`.
+        let explicit = features.values()
+            .flat_map(|v| v.iter())
+            .filter(|f| f.starts_with("dep:"))
+            .map(|f| f[4..].to_string())
+            .collect::<BTreeSet<_>>();
+        for dep in optional_deps(value) {
+            if !explicit.contains(&dep) {
+                features.entry(dep).or_insert(vec![]);
+            }
+        }
+
+        let mut targets = vec![];
+        let lib_name = name.replace("-", "_");
+        match value.get("lib") {
+            Some(lib) => {
+                let path = lib.get("path").and_then(|p| p.as_str())
+                    .map_or(root.join("src/lib.rs"), |p| root.join(p));
+                let name = lib.get("name").and_then(|n| n.as_str()).unwrap_or(&lib_name);
+                targets.push(Target { kind: TargetKind::Lib, name: name.to_string(),
+                                      path, required_features: vec![] })
+            }
+            None => if root.join("src/lib.rs").is_file() {
+                targets.push(Target { kind: TargetKind::Lib, name: lib_name,
+                                      path: root.join("src/lib.rs"), required_features: vec![] })
+            }
+        }
+
+        for &(kind, key) in [(TargetKind::Bin, "bin"), (TargetKind::Example, "example"),
+                             (TargetKind::Test, "test")].iter() {
+            let explicit = value.get(key).and_then(|t| t.as_array()).map_or(&[][..], |a| &a[..]);
+            let auto = package.get(kind.auto_dir().unwrap().1)
+                .and_then(|a| a.as_bool()).unwrap_or(true);
+            targets.extend(find_targets(kind, explicit, auto, &name, root)?);
+        }
+
+        Ok(Package {
+            name,
+            version: version.to_string(),
+            root: root.to_path_buf(),
+            features,
+            targets,
+        })
+    }
+
+    /// The features enabled by the feature flags: those requested
+    /// (ignoring any this package doesn't have), or all of them, along
+    /// with `default` if `default` is true, and everything those
+    /// enable in turn.
+    pub fn enabled_features(&self, requested: &[String], all: bool,
+                            default: bool) -> BTreeSet<String> {
+        let mut todo = if all {
+            self.features.keys().cloned().collect::<Vec<_>>()
+        } else {
+            requested.iter().filter(|f| self.features.contains_key(*f)).cloned().collect()
+        };
+        if default && self.features.contains_key("default") {
+            todo.push("default".to_string());
+        }
+
+        let mut enabled = BTreeSet::new();
+        while let Some(feature) = todo.pop() {
+            if !enabled.insert(feature.clone()) { continue }
+            for f in self.features.get(&feature).into_iter().flat_map(|v| v.iter()) {
+                if f.starts_with("dep:") { continue }
+                // `dep/feature` enables the feature of the dependency,
+                // and the dependency itself if it's optional, unless
+                // it is written `dep?/feature`.
+                let f = match f.find('/') {
+                    Some(i) if f[..i].ends_with('?') => continue,
+                    Some(i) => &f[..i],
+                    None => &f[..],
+                };
+                if self.features.contains_key(f) {
+                    todo.push(f.to_string())
+                }
+            }
+        }
+        enabled
+    }
+
+    /// The configuration `target` is compiled with when the features
+    /// `features` are enabled: those of the host, a `feature = "..."`
+    /// for each feature, and `test` for a test.
+    pub fn cfg(&self, target: &Target, features: &BTreeSet<String>) -> Cfg {
+        let mut cfg = Cfg::host();
+        for f in features.iter() {
+            cfg.set_value("feature", f);
+        }
+        if target.kind == TargetKind::Test {
+            cfg.set("test");
+        }
+        cfg
+    }
+}
+
+/// A workspace, or a package on its own.
+#[derive(Clone, Debug)]
+pub struct Workspace {
+    /// The directory of the root manifest.
+    pub root: PathBuf,
+    /// The members.
+    pub packages: Vec<Package>,
+}
+
+impl Workspace {
+    /// Read the workspace with root manifest `manifest`: if it has a
+    /// `[workspace]` section, the package it describes (if any) and
+    /// all its members, otherwise just the one package.
+    pub fn load(manifest: &Path) -> Result<Workspace, String> {
+        let value = config::read_toml(manifest)?;
+        let root = manifest.parent().unwrap_or(Path::new(".")).to_path_buf();
+
+        let mut packages = vec![];
+        let workspace = value.get("workspace");
+        if value.get("package").is_some() || workspace.is_none() {
+            packages.push(Package::from_toml(&value, &root)
+                          .map_err(|e| format!("{}: {}", manifest.display(), e))?);
+        }
+
+        if let Some(workspace) = workspace {
+            let members = strings("members", workspace.get("members")
+                                  .unwrap_or(&toml::Value::Array(vec![])))?;
+            let exclude = strings("exclude", workspace.get("exclude")
+                                  .unwrap_or(&toml::Value::Array(vec![])))?;
+            let exclude = exclude.iter().map(|e| root.join(e)).collect::<Vec<_>>();
+
+            for pattern in members.iter() {
+                for dir in expand_glob(&root, pattern) {
+                    let manifest = dir.join("Cargo.toml");
+                    if exclude.iter().any(|e| dir.starts_with(e)) || !manifest.is_file() ||
+                            packages.iter().any(|p: &Package| p.root == dir) {
+                        continue
+                    }
+                    packages.push(Package::load(&manifest)?);
+                }
+            }
+        }
+        Ok(Workspace { root, packages })
+    }
+}
+
+/// Find the manifest that Cargo would use if run in `dir`: the closest
+/// `Cargo.toml` in it or one of its parents.
+pub fn find_manifest(dir: &Path) -> Option<PathBuf> {
+    let mut dir = Some(dir);
+    while let Some(d) = dir {
+        let manifest = d.join("Cargo.toml");
+        if manifest.is_file() {
+            return Some(manifest)
+        }
+        dir = d.parent();
+    }
+    None
+}
+
+/// The names of the optional dependencies in the manifest `value`.
+fn optional_deps(value: &toml::Value) -> Vec<String> {
+    let mut tables = vec![value.get("dependencies")];
+    if let Some(targets) = value.get("target").and_then(|t| t.as_table()) {
+        tables.extend(targets.values().map(|t| t.get("dependencies")));
+    }
+    tables.into_iter()
+        .filter_map(|t| t.and_then(|t| t.as_table()))
+        .flat_map(|t| t.iter())
+        .filter(|&(_, dep)| dep.get("optional").and_then(|o| o.as_bool()) == Some(true))
+        .map(|(name, _)| name.clone())
+        .collect()
+}
+
+/// The targets of kind `kind`: those in the manifest sections
+/// `explicit`, and, if `auto` is true, those in the usual places that
+/// aren't mentioned in a section.
+fn find_targets(kind: TargetKind, explicit: &[toml::Value], auto: bool,
+                package: &str, root: &Path) -> Result<Vec<Target>, String> {
+    let dir = root.join(kind.auto_dir().unwrap().0);
+    let mut targets = vec![];
+    for t in explicit.iter() {
+        let name = match t.get("name").and_then(|n| n.as_str()) {
+            Some(n) => n.to_string(),
+            None => return Err(format!("a `[[{}]]` has no name", kind.name()))
+        };
+        let path = match t.get("path").and_then(|p| p.as_str()) {
+            Some(p) => root.join(p),
+            None if kind == TargetKind::Bin && name == package &&
+                    root.join("src/main.rs").is_file() => root.join("src/main.rs"),
+            None if dir.join(format!("{}.rs", name)).is_file() => {
+                dir.join(format!("{}.rs", name))
+            }
+            None => dir.join(&name).join("main.rs"),
+        };
+        let required = match t.get("required-features") {
+            Some(r) => strings("required-features", r)?,
+            None => vec![]
+        };
+        targets.push(Target { kind, name, path, required_features: required });
+    }
+    if !auto {
+        return Ok(targets)
+    }
+
+    let mut found = vec![];
+    if kind == TargetKind::Bin && root.join("src/main.rs").is_file() {
+        found.push((package.to_string(), root.join("src/main.rs")));
+    }
+    let mut entries = match fs::read_dir(&dir) {
+        Ok(entries) => entries.filter_map(|e| e.ok()).map(|e| e.path()).collect::<Vec<_>>(),
+        Err(_) => vec![]
+    };
+    entries.sort();
+    for path in entries {
+        let name = match path.file_stem().and_then(|s| s.to_str()) {
+            Some(n) => n.to_string(),
+            None => continue
+        };
+        if path.is_dir() && path.join("main.rs").is_file() {
+            found.push((name, path.join("main.rs")));
+        } else if path.extension().is_some_and(|e| e == "rs") {
+            found.push((name, path));
+        }
+    }
+
+    for (name, path) in found {
+        if targets.iter().any(|t| t.name == name || t.path == path) { continue }
+        targets.push(Target { kind, name, path, required_features: vec![] });
+    }
+    Ok(targets)
+}
+
+/// The directories matching `pattern` (relative to `root`), in which
+/// each component may be a glob, as understood by `glob_matches`.
+fn expand_glob(root: &Path, pattern: &str) -> Vec<PathBuf> {
+    let mut dirs = vec![root.to_path_buf()];
+    for component in pattern.split('/').filter(|c| !c.is_empty() && *c != ".") {
+        if !component.contains('*') && !component.contains('?') {
+            dirs = dirs.into_iter().map(|d| d.join(component)).collect();
+            continue
+        }
+        let mut next = vec![];
+        for d in dirs.iter() {
+            let mut entries = match fs::read_dir(d) {
+                Ok(entries) => entries.filter_map(|e| e.ok()).collect::<Vec<_>>(),
+                Err(_) => continue
+            };
+            entries.sort_by_key(|e| e.file_name());
+            for e in entries {
+                let matches = e.file_name().to_str().is_some_and(|n| {
+                    glob_matches(component, n)
+                });
+                if matches && e.path().is_dir() {
+                    next.push(e.path())
+                }
+            }
+        }
+        dirs = next;
+    }
+    dirs
+}
+
+#[test]
+fn test_package() {
+    let manifest = r#"
+        [package]
+        name = "foo-bar"
+        version = "1.2.3"
+        autotests = false
+
+        [features]
+        default = ["std"]
+        std = ["serde?/std", "log/std"]
+        extra = ["dep:rand"]
+
+        [dependencies]
+        log = { version = "0.4", optional = true }
+        serde = { version = "1", optional = true }
+        rand = { version = "0.8", optional = true }
+
+        [[bin]]
+        name = "tool"
+        path = "tools/tool.rs"
+        required-features = ["extra"]
+
+        [[test]]
+        name = "it"
+    "#.parse::<toml::Value>().unwrap();
+    let root = Path::new("/nonexistent");
+    let package = Package::from_toml(&manifest, root).unwrap();
+    assert_eq!((&*package.name, &*package.version), ("foo-bar", "1.2.3"));
+
+    let features = package.features.keys().map(|s| &**s).collect::<Vec<_>>();
+    assert_eq!(features, ["default", "extra", "log", "serde", "std"]);
+    let enabled = package.enabled_features(&[], false, true);
+    assert_eq!(enabled.iter().map(|s| &**s).collect::<Vec<_>>(), ["default", "log", "std"]);
+    let enabled = package.enabled_features(&["extra".to_string(), "nope".to_string()],
+                                           false, false);
+    assert_eq!(enabled.iter().map(|s| &**s).collect::<Vec<_>>(), ["extra"]);
+
+    let targets = package.targets.iter()
+        .map(|t| (t.kind, &*t.name, t.path.strip_prefix(root).unwrap().to_path_buf()))
+        .collect::<Vec<_>>();
+    assert_eq!(targets, [(TargetKind::Bin, "tool", PathBuf::from("tools/tool.rs")),
+                         (TargetKind::Test, "it", PathBuf::from("tests/it/main.rs"))]);
+    assert_eq!(package.targets[0].required_features, ["extra"]);
+
+    let cfg = package.cfg(&package.targets[1], &enabled);
+    assert!(cfg.is_set("feature", Some("extra")) && cfg.is_set("test", None));
+}
@@ -0,0 +1,178 @@
+//! Evaluating `#[cfg(...)]` attributes, so that only the items that
+//! would actually be compiled (for some set of features, say) are
+//! checked.
+
+use std::collections::HashSet;
+
+use syn;
+
+/// A set of configuration options, like those given to `rustc` with
+/// `--cfg`: names (`unix`, `test`), and names with values
+/// (`feature = "foo"`).
+#[derive(Clone, Debug, Default)]
+pub struct Cfg {
+    options: HashSet<(String, Option<String>)>,
+}
+
+impl Cfg {
+    /// Create a configuration with nothing set.
+    pub fn new() -> Cfg {
+        Cfg { options: HashSet::new() }
+    }
+
+    /// The configuration of the platform spellck itself was built
+    /// for, like the compiler sets when not cross-compiling, along
+    /// with `debug_assertions`, as for a debug build.
+    pub fn host() -> Cfg {
+        let mut cfg = Cfg::new();
+        cfg.set("debug_assertions");
+        if cfg!(unix) {
+            cfg.set("unix");
+            cfg.set_value("target_family", "unix");
+        }
+        if cfg!(windows) {
+            cfg.set("windows");
+            cfg.set_value("target_family", "windows");
+        }
+
+        let oses = [("linux", cfg!(target_os = "linux")),
+                    ("macos", cfg!(target_os = "macos")),
+                    ("windows", cfg!(target_os = "windows")),
+                    ("android", cfg!(target_os = "android")),
+                    ("ios", cfg!(target_os = "ios")),
+                    ("freebsd", cfg!(target_os = "freebsd")),
+                    ("dragonfly", cfg!(target_os = "dragonfly")),
+                    ("netbsd", cfg!(target_os = "netbsd")),
+                    ("openbsd", cfg!(target_os = "openbsd"))];
+        let arches = [("x86", cfg!(target_arch = "x86")),
+                      ("x86_64", cfg!(target_arch = "x86_64")),
+                      ("arm", cfg!(target_arch = "arm")),
+                      ("aarch64", cfg!(target_arch = "aarch64")),
+                      ("mips", cfg!(target_arch = "mips")),
+                      ("powerpc", cfg!(target_arch = "powerpc")),
+                      ("powerpc64", cfg!(target_arch = "powerpc64"))];
+        let envs = [("gnu", cfg!(target_env = "gnu")),
+                    ("msvc", cfg!(target_env = "msvc")),
+                    ("musl", cfg!(target_env = "musl"))];
+        for &(name, values) in [("target_os", &oses[..]),
+                                ("target_arch", &arches[..]),
+                                ("target_env", &envs[..])].iter() {
+            for &(value, set) in values.iter() {
+                if set { cfg.set_value(name, value) }
+            }
+        }
+
+        cfg.set_value("target_pointer_width",
+                      if cfg!(target_pointer_width = "64") { "64" } else { "32" });
+        cfg.set_value("target_endian",
+                      if cfg!(target_endian = "big") { "big" } else { "little" });
+        cfg
+    }
+
+    /// Set the option `name`.
+    pub fn set(&mut self, name: &str) {
+        self.options.insert((name.to_string(), None));
+    }
+
+    /// Set the option `name = "value"`.
+    pub fn set_value(&mut self, name: &str, value: &str) {
+        self.options.insert((name.to_string(), Some(value.to_string())));
+    }
+
+    /// Set the option given as the argument of `--cfg`, either `name`
+    /// or `name="value"`.
+    pub fn set_spec(&mut self, spec: &str) {
+        match spec.find('=') {
+            Some(i) => {
+                let value = spec[i + 1..].trim();
+                let value = value.trim_matches('"');
+                self.set_value(spec[..i].trim(), value)
+            }
+            None => self.set(spec.trim()),
+        }
+    }
+
+    /// Whether the option `name`, or `name = "value"`, is set.
+    pub fn is_set(&self, name: &str, value: Option<&str>) -> bool {
+        self.options.contains(&(name.to_string(), value.map(|v| v.to_string())))
+    }
+
+    /// Whether something with attributes `attrs` is compiled, i.e. all
+    /// of its `#[cfg(...)]` predicates hold.
+    ///
+    /// A predicate that can't be understood is taken to hold, so that
+    /// anything unusual is checked rather than silently skipped.
+    pub fn enabled(&self, attrs: &[syn::Attribute]) -> bool {
+        attrs.iter().filter(|a| a.path.is_ident("cfg")).all(|a| {
+            match a.parse_meta() {
+                Ok(syn::Meta::List(ref list)) if list.nested.len() == 1 => {
+                    self.eval(&list.nested[0]).unwrap_or(true)
+                }
+                _ => true
+            }
+        })
+    }
+
+    /// Evaluate a single predicate, `None` if it is malformed.
+    fn eval(&self, pred: &syn::NestedMeta) -> Option<bool> {
+        let meta = match *pred {
+            syn::NestedMeta::Meta(ref m) => m,
+            syn::NestedMeta::Lit(_) => return None,
+        };
+        match *meta {
+            syn::Meta::Path(ref p) => {
+                p.get_ident().map(|name| self.is_set(&name.to_string(), None))
+            }
+            syn::Meta::NameValue(ref nv) => {
+                match (nv.path.get_ident(), &nv.lit) {
+                    (Some(name), syn::Lit::Str(s)) => {
+                        Some(self.is_set(&name.to_string(), Some(&s.value())))
+                    }
+                    _ => None
+                }
+            }
+            syn::Meta::List(ref list) => {
+                let name = match list.path.get_ident() {
+                    Some(name) => name.to_string(),
+                    None => return None
+                };
+                let mut values = vec![];
+                for p in list.nested.iter() {
+                    match self.eval(p) {
+                        Some(b) => values.push(b),
+                        None => return None
+                    }
+                }
+                match &*name {
+                    "all" => Some(values.iter().all(|&b| b)),
+                    "any" => Some(values.iter().any(|&b| b)),
+                    "not" if values.len() == 1 => Some(!values[0]),
+                    _ => None
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_cfg() {
+    fn enabled(cfg: &Cfg, item: &str) -> bool {
+        let item: syn::ItemFn = syn::parse_str(item).unwrap();
+        cfg.enabled(&item.attrs)
+    }
+
+    let mut cfg = Cfg::new();
+    cfg.set("unix");
+    cfg.set_spec("feature=\"foo\"");
+
+    assert!(enabled(&cfg, "fn f() {}"));
+    assert!(enabled(&cfg, "#[cfg(unix)] fn f() {}"));
+    assert!(!enabled(&cfg, "#[cfg(windows)] fn f() {}"));
+    assert!(enabled(&cfg, "#[cfg(feature = \"foo\")] fn f() {}"));
+    assert!(!enabled(&cfg, "#[cfg(feature = \"bar\")] fn f() {}"));
+    assert!(enabled(&cfg, "#[cfg(all(unix, not(feature = \"bar\")))] fn f() {}"));
+    assert!(!enabled(&cfg, "#[cfg(any(test, windows))] fn f() {}"));
+    assert!(!enabled(&cfg, "#[cfg(unix)] #[cfg(test)] fn f() {}"));
+    // not understood, so kept.
+    assert!(enabled(&cfg, "#[cfg(foo(unix))] fn f() {}"));
+}
@@ -0,0 +1,524 @@
+//! Checking identifiers and documentation for misspellings,
+//! independently of how a crate is read.
+//!
+//! The frontends (the syn-based `source`, and the compiler plugin's
+//! `visitor`) find the names and documentation that are part of a
+//! public API, and hand them to a `Checker`; positions are byte
+//! offsets in whatever the frontend is counting in.
+//!
+//! Other tools can use a `Checker` directly too, or just call
+//! `check_identifier` and `check_prose`, which use the built-in
+//! dictionaries:
+//!
+//! ```rust
+//! let found = spellck::check_prose("Recieve a mesage.");
+//! assert_eq!(found[0].word, "Recieve");
+//! assert_eq!(found[0].span, Some(spellck::Span { lo: 0, hi: 7 }));
+//! ```
+
+use std::collections::HashSet;
+use std::mem;
+
+use words;
+use builtin;
+use markdown;
+use suggest;
+use dict::{self, Dictionary};
+use config::{Config, ItemKind};
+use wordmap::{self, WordMap};
+use stem;
+
+/// A range of bytes, `lo..hi`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Span {
+    pub lo: usize,
+    pub hi: usize,
+}
+
+/// Why a word was reported.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Kind {
+    /// The word isn't in the dictionary.
+    Unknown,
+    /// An identifier made of dictionary words run together without a
+    /// separator, like `readfile`; these are the words.
+    MissingSeparator(Vec<String>),
+    /// The word has to be capitalised like this in prose (e.g.
+    /// `GitHub`), and isn't.
+    Capitalization(String),
+    /// The word is on the deny list, with this replacement, if there
+    /// is one.
+    Forbidden(Option<String>),
+    /// The word is a common misspelling of this (or of one of these
+    /// alternatives, separated by commas).
+    KnownMisspelling(String),
+}
+
+impl Kind {
+    /// What a word of this kind is called in a diagnostic.
+    pub fn heading(&self) -> &'static str {
+        match *self {
+            Kind::Unknown | Kind::MissingSeparator(_) => "misspelled word",
+            Kind::Capitalization(_) => "wrongly capitalized word",
+            Kind::Forbidden(_) => "forbidden word",
+            Kind::KnownMisspelling(_) => "known misspelling",
+        }
+    }
+
+    /// The name of the lint that reports words of this kind.
+    pub fn lint_name(&self) -> &'static str {
+        match *self {
+            Kind::Forbidden(_) => "forbidden_words",
+            Kind::KnownMisspelling(_) => "known_misspellings",
+            _ => "misspellings",
+        }
+    }
+}
+
+/// A single misspelled word.
+#[derive(Clone, Debug)]
+pub struct Misspelling {
+    /// The word, as it appears in the source.
+    pub word: String,
+    /// What is wrong with it.
+    pub kind: Kind,
+    /// The exact location of the word, if it is known. This is only
+    /// the case for words in doc comments (`///` etc.), where the
+    /// text of the documentation is exactly the source text.
+    pub span: Option<Span>,
+}
+
+/// An edit that can be applied mechanically to fix a misspelling:
+/// the text covered by `span` should be replaced by `replacement`.
+#[derive(Clone, Debug)]
+pub struct Replacement {
+    pub span: Span,
+    pub replacement: String,
+}
+
+impl Misspelling {
+    /// The edit that fixes this misspelling, if the word can be
+    /// located exactly and `words` has a single confident correction
+    /// for it (or it just needs capitalising properly).
+    pub fn replacement(&self, words: &dyn Dictionary) -> Option<Replacement> {
+        self.span.and_then(|sp| {
+            let r = match self.kind {
+                Kind::Unknown => suggest::correction(&self.word, words),
+                Kind::MissingSeparator(_) => None,
+                Kind::Capitalization(ref c) => Some(c.clone()),
+                Kind::Forbidden(ref r) => r.clone().and_then(|r| {
+                    if wordmap::is_single(&r) { Some(r) } else { None }
+                }),
+                Kind::KnownMisspelling(ref r) if wordmap::is_single(r) => Some(r.clone()),
+                Kind::KnownMisspelling(_) => None,
+            };
+            r.map(|r| Replacement { span: sp, replacement: r })
+        })
+    }
+
+    /// Describe the misspelling for a diagnostic, along with any
+    /// suggestions from `words`, e.g. "dok (did you mean `doc`?)".
+    pub fn describe(&self, words: &dyn Dictionary) -> String {
+        match self.kind {
+            Kind::Unknown => {
+                let suggestions = suggest::suggestions(&self.word, words,
+                                                       suggest::DEFAULT_SUGGESTIONS);
+                suggest::describe(&self.word, &suggestions)
+            }
+            Kind::MissingSeparator(ref parts) => {
+                let quoted = parts.iter().map(|p| format!("`{}`", p)).collect::<Vec<_>>();
+                format!("{} (missing a separator between {})", self.word, quoted.join(" and "))
+            }
+            Kind::Capitalization(ref c) => format!("{} (should be `{}`)", self.word, c),
+            Kind::Forbidden(None) => self.word.clone(),
+            Kind::Forbidden(Some(ref r)) => format!("{} (use `{}` instead)", self.word, r),
+            Kind::KnownMisspelling(ref r) => {
+                let alternatives = wordmap::alternatives(r).iter()
+                    .map(|a| a.to_string()).collect::<Vec<_>>();
+                suggest::describe(&self.word, &alternatives)
+            }
+        }
+    }
+}
+
+/// Split the misspellings at one position into groups that share a
+/// heading (see `Kind::heading`), and so can be reported together.
+pub fn group_by_heading(misspellings: &[Misspelling]) -> Vec<(&'static str, Vec<&Misspelling>)> {
+    let mut groups: Vec<(&'static str, Vec<&Misspelling>)> = vec![];
+    for m in misspellings.iter() {
+        let heading = m.kind.heading();
+        match groups.iter().position(|&(h, _)| h == heading) {
+            Some(i) => groups[i].1.push(m),
+            None => groups.push((heading, vec![m]))
+        }
+    }
+    groups
+}
+
+/// Checks words against a dictionary, according to a configuration.
+pub struct Checker<'a> {
+    /// The reference dictionary.
+    words: &'a dyn Dictionary,
+
+    /// The correct acronyms.
+    acronyms: HashSet<String>,
+
+    /// The words that aren't allowed.
+    forbidden: WordMap,
+
+    /// Common misspellings, and their corrections.
+    known_misspellings: WordMap,
+
+    /// What to check, and what to skip.
+    config: Config,
+
+    /// The misspellings found by the current check.
+    found: Vec<Misspelling>,
+}
+
+impl<'a> Checker<'a> {
+    /// Create a checker that checks against `words`, according to
+    /// `config`.
+    pub fn new(words: &'a dyn Dictionary, config: Config) -> Checker<'a> {
+        let mut acronyms = builtin::acronyms();
+        acronyms.extend(config.acronyms.iter().cloned());
+        Checker {
+            words,
+            acronyms,
+            forbidden: WordMap::new(),
+            known_misspellings: builtin::misspellings(),
+            config,
+            found: vec![],
+        }
+    }
+
+    /// The configuration being checked with.
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Report any use of the words in `forbidden`, whether or not
+    /// they are spelled correctly.
+    pub fn set_forbidden(&mut self, forbidden: WordMap) {
+        self.forbidden = forbidden;
+    }
+
+    /// Report the misspellings in `known_misspellings` (which replace
+    /// the built-in ones, `builtin::misspellings`) as known
+    /// misspellings, with their correction, even if they're in the
+    /// dictionary. Entries without a correction are ignored.
+    pub fn set_known_misspellings(&mut self, known_misspellings: WordMap) {
+        self.known_misspellings = known_misspellings;
+    }
+
+    /// Check the name of an item, of the given kind if it is one that
+    /// can be configured. The misspellings have no span, since an
+    /// identifier has to be changed everywhere it's used, not just
+    /// where it's declared.
+    pub fn check_ident(&mut self, ident: &str, kind: Option<ItemKind>) -> Vec<Misspelling> {
+        if !kind.is_none_or(|k| self.config.checks(k)) { return vec![] }
+        self.check_ident_at(ident, None);
+        mem::take(&mut self.found)
+    }
+
+    /// Check an identifier on its own, like `check_ident`, but with
+    /// the span of each misspelling as the byte range of the word in
+    /// `ident`.
+    pub fn check_identifier(&mut self, ident: &str) -> Vec<Misspelling> {
+        self.check_ident_at(ident, Some(0));
+        mem::take(&mut self.found)
+    }
+
+    /// Check some plain text (not Markdown), with the span of each
+    /// misspelling as the byte range of the word in `text`.
+    pub fn check_prose(&mut self, text: &str) -> Vec<Misspelling> {
+        self.check_prose_at(text, Some(0));
+        mem::take(&mut self.found)
+    }
+
+    /// Check the documentation of an item, given as the text of each
+    /// of its doc attributes, in order, along with the position the
+    /// text starts at in the source, if it is exactly the source text
+    /// (as for `///` comments, but not `#[doc = "..."]`, which may
+    /// have escapes). Only the prose of the Markdown is checked, not
+    /// code, URLs or HTML. Each misspelling is returned with the
+    /// index of the attribute it is in.
+    pub fn check_docs(&mut self, docs: &[(&str, Option<usize>)]) -> Vec<(usize, Misspelling)> {
+        if !self.config.checks(ItemKind::Docs) { return vec![] }
+
+        // the Markdown is parsed as a whole, since e.g. a code block
+        // covers many `///` lines, each of which is an attribute.
+        let doc = markdown::Doc::new(docs.iter().map(|&(s, _)| s));
+
+        let mut ret = vec![];
+        for prose in doc.prose() {
+            let lo = docs[prose.attr].1.map(|lo| lo + prose.offset);
+            self.check_prose_at(prose.text, lo);
+            for m in mem::take(&mut self.found) {
+                ret.push((prose.attr, m))
+            }
+        }
+        ret
+    }
+
+    /// Check an identifier, which starts at `lo` if that is known.
+    fn check_ident_at(&mut self, ident: &str, lo: Option<usize>) {
+        // secret rust internals, e.g. __std_macros
+        if ident.starts_with("__") || self.config.ignores(ident) { return }
+
+        // the ident itself is correct, so shortcircuit to avoid doing
+        // any of the submatching done below (other than looking for
+        // listed words).
+        if self.raw_word_is_correct(ident) {
+            for (offset, w) in words::subword_indices(ident) {
+                self.check_listed(w, lo.map(|lo| lo + offset));
+            }
+        } else {
+            self.check_subwords(ident, lo, true);
+        }
+    }
+
+    /// Report `w` if it is a forbidden word or a known misspelling,
+    /// returning whether it was.
+    fn check_listed(&mut self, w: &str, lo: Option<usize>) -> bool {
+        let kind = match self.forbidden.get(w) {
+            Some(replacement) => Kind::Forbidden(replacement),
+            None => match self.known_misspellings.get(w) {
+                Some(Some(correction)) => Kind::KnownMisspelling(correction),
+                _ => return false
+            }
+        };
+        self.report(w, kind, lo);
+        true
+    }
+
+    /// Checks if the given string is a correct "word", without
+    /// splitting it at all. Any word that isn't entirely alphabetic
+    /// is automatically considered a proper word. Acronyms (`HTTP`,
+    /// `URLs`) are also checked against the list of acronyms.
+    fn raw_word_is_correct(&mut self, w: &str) -> bool {
+        let w = &*dict::normalize(w);
+        words::acronym(w).is_some_and(|a| self.acronyms.contains(a)) ||
+            self.words.contains(w) ||
+            (w.chars().all(|c| c.is_alphabetic()) && {
+                let lower = dict::normalize(&w.to_lowercase());
+                self.words.contains(&lower) ||
+                self.stemmed_word_is_correct(&lower)
+            })
+    }
+
+    /// Checks if a word with digits, like `utf8`, is correct as a
+    /// whole.
+    fn token_is_correct(&self, w: &str) -> bool {
+        let w = dict::normalize(w);
+        self.words.contains(&w) || self.words.contains(&dict::normalize(&w.to_lowercase()))
+    }
+
+    /// Checks if `w` is a word on its own when it's a piece of an
+    /// identifier without separators (`read` in `readfile`). Almost
+    /// any misspelling can be split into some short words, or words
+    /// with some stem, so this is strict: only exact matches, and
+    /// only common short words (`mispelled` isn't `mi` and `spelled`).
+    fn segment_is_word(&self, w: &str) -> bool {
+        let lower = dict::normalize(&w.to_lowercase());
+        (lower.chars().count() >= 3 || SHORT_SEGMENTS.contains(&&*lower)) &&
+            (self.words.contains(&dict::normalize(w)) || self.words.contains(&lower))
+    }
+
+    fn stemmed_word_is_correct(&self, w: &str) -> bool {
+        stem::get(w).ok().is_some_and(|s| self.words.contains(&s))
+    }
+
+    /// Check a word for correctness, including splitting `foo_bar`
+    /// and `FooBar` into `foo` & `bar` and `Foo` & `Bar`
+    /// respectively, and `x86_64` into `x86` (or just `x`, if `x86`
+    /// isn't a word). This records any incorrect word(s). If `lo` is
+    /// given, `w` is exactly the source text starting at that
+    /// position, and so each word can be given a precise span. For an
+    /// identifier (`ident`), a word that is several words run together
+    /// (`readfile`) is accepted, unless the configuration asks for
+    /// those to be reported.
+    fn check_subwords(&mut self, w: &str, lo: Option<usize>, ident: bool) {
+        for (offset, w) in words::token_indices(w) {
+            // `utf8` and `u64` are words in their own right, but
+            // otherwise the digits are just a separator.
+            let letters = w.trim_end_matches(|c: char| c.is_numeric());
+            if letters.len() < w.len() && self.token_is_correct(w) {
+                continue
+            }
+            let w = letters;
+            let word_lo = lo.map(|lo| lo + offset);
+            if self.check_listed(w, word_lo) || self.config.known_misspellings_only {
+                continue
+            }
+
+            if !self.config.ignores(w) && !self.raw_word_is_correct(w) {
+                let segments = if ident && w.chars().all(|c| c.is_alphabetic()) {
+                    words::segment(w, |part| self.segment_is_word(part))
+                        .and_then(|parts| {
+                            if parts.len() <= MAX_SEGMENTS { Some(parts) } else { None }
+                        })
+                } else {
+                    None
+                };
+                let kind = match segments {
+                    None => Kind::Unknown,
+                    Some(_) if !self.config.report_missing_separators => continue,
+                    Some(parts) => Kind::MissingSeparator(parts.iter().map(|p| p.to_string())
+                                                               .collect()),
+                };
+
+                self.report(w, kind, word_lo);
+            }
+        }
+    }
+
+    /// Record `w` as misspelled, at its exact position if it is known
+    /// (it starts at `lo`).
+    fn report(&mut self, w: &str, kind: Kind, lo: Option<usize>) {
+        let span = lo.map(|lo| Span { lo, hi: lo + w.len() });
+        self.found.push(Misspelling { word: w.to_string(), kind, span });
+    }
+
+    /// Check some prose for misspellings, as for `check_subwords`,
+    /// but splitting it into words in the way English is written, so
+    /// that contractions, possessives and hyphenated words can be
+    /// recognised.
+    fn check_prose_at(&mut self, text: &str, lo: Option<usize>) {
+        for (offset, word) in words::prose_word_indices(text) {
+            let lo = lo.map(|lo| lo + offset);
+            // a hyphenated word is correct as a whole, or if each part is.
+            if !word.contains('-') || !self.prose_word_is_correct(word) {
+                let mut part_offset = 0;
+                for part in word.split('-') {
+                    self.check_prose_word(part, lo.map(|lo| lo + part_offset));
+                    part_offset += part.len() + 1;
+                }
+            }
+        }
+    }
+
+    /// Check a single word of prose, which has no hyphens.
+    fn check_prose_word(&mut self, word: &str, lo: Option<usize>) {
+        if self.config.ignores(word) { return }
+        // only report the word underneath a possessive or contraction,
+        // if that's the problem; anything else is an identifier
+        // (`foo_bar`, `x86_64`) or a word with a stray apostrophe.
+        let base = words::strip_clitic(word).unwrap_or(word);
+        if self.check_listed(base, lo) || self.config.known_misspellings_only {
+            return
+        }
+
+        // a word with a required capitalisation is only correct with
+        // that, even if some other dictionary has it in lowercase.
+        let normal = dict::normalize(base);
+        if let Some(required) = self.words.required_case(&dict::normalize(&normal.to_lowercase())) {
+            if normal != required {
+                self.report(base, Kind::Capitalization(required), lo);
+            }
+            return
+        }
+
+        if !self.prose_word_is_correct(word) {
+            self.check_subwords(base, lo, false)
+        }
+    }
+
+    /// Checks if a word of prose is correct as it is, including
+    /// possessives (`crate's`) and contractions (`don't`, `they'll`).
+    fn prose_word_is_correct(&mut self, word: &str) -> bool {
+        let normal = dict::normalize(&word.replace('\u{2019}', "'"));
+        self.words.contains(&normal) ||
+            self.words.contains(&dict::normalize(&normal.to_lowercase())) ||
+            words::is_contraction(word) ||
+            words::strip_clitic(word).is_some_and(|base| self.raw_word_is_correct(base))
+    }
+}
+
+/// The dictionary used by `check_identifier` and `check_prose`: the
+/// Rust words, and the bundled English words if spellck was built
+/// with them (the `bundled-dict` feature).
+pub fn default_words() -> dict::Chain<'static> {
+    let mut words = dict::Chain::new();
+    words.push(builtin::rust());
+    if let Some(english) = builtin::english() {
+        words.push(english);
+    }
+    words
+}
+
+/// The most words that an identifier without separators is split
+/// into; any more, and it's more likely to be a misspelling.
+const MAX_SEGMENTS: usize = 3;
+
+/// The words shorter than three letters that can be part of an
+/// identifier without separators (`isempty`, `toupper`).
+static SHORT_SEGMENTS: &[&str] = &[
+    "as", "at", "by", "do", "if", "in", "is", "it", "no", "of", "on", "or", "to", "up",
+];
+
+thread_local!(static DEFAULT_WORDS: dict::Chain<'static> = default_words());
+
+/// Check an identifier against the built-in dictionaries (see
+/// `default_words`), with the default configuration, giving the byte
+/// range of each misspelled word in `ident`.
+pub fn check_identifier(ident: &str) -> Vec<Misspelling> {
+    DEFAULT_WORDS.with(|words| Checker::new(words, Config::default()).check_identifier(ident))
+}
+
+/// Check some plain text against the built-in dictionaries (see
+/// `default_words`), with the default configuration, giving the byte
+/// range of each misspelled word in `text`.
+pub fn check_prose(text: &str) -> Vec<Misspelling> {
+    DEFAULT_WORDS.with(|words| Checker::new(words, Config::default()).check_prose(text))
+}
+
+#[test]
+fn test_checker() {
+    let words = ["bad", "doc", "comment", "read", "file", "the"].iter()
+        .map(|w| w.to_string()).collect::<HashSet<_>>();
+    let mut checker = Checker::new(&words, Config::default());
+
+    let found = checker.check_ident("read_fiel", Some(ItemKind::Fn));
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].word, "fiel");
+    assert_eq!(found[0].kind, Kind::Unknown);
+    assert!(found[0].span.is_none());
+    assert!(checker.check_ident("readfile", None).is_empty());
+
+    let found = checker.check_docs(&[("/// Bad doc", Some(100)),
+                                     ("/// coment, recieve the", Some(120))]);
+    let found = found.iter().map(|&(i, ref m)| (i, &*m.word, m.kind.clone(), m.span))
+                     .collect::<Vec<_>>();
+    assert_eq!(found, vec![(1, "coment", Kind::Unknown, Some(Span { lo: 124, hi: 130 })),
+                           (1, "recieve", Kind::KnownMisspelling("receive".to_string()),
+                            Some(Span { lo: 132, hi: 139 }))]);
+
+    let found = checker.check_identifier("ReadFielRecieve");
+    let found = found.iter().map(|m| (&*m.word, m.span.unwrap())).collect::<Vec<_>>();
+    assert_eq!(found, vec![("Fiel", Span { lo: 4, hi: 8 }), ("Recieve", Span { lo: 8, hi: 15 })]);
+    let found = checker.check_prose("the coment");
+    assert_eq!(found[0].span, Some(Span { lo: 4, hi: 10 }));
+}
+
+#[test]
+fn test_default_words() {
+    assert!(check_prose("utf-8 bytes, UTF-16 and utf8").is_empty());
+    assert!(check_identifier("x86_64_linux_u64").is_empty());
+}
+
+#[test]
+fn test_missing_separators() {
+    assert!(check_identifier("readfile").is_empty());
+    assert!(check_identifier("isempty").is_empty());
+    assert!(check_identifier("toupper").is_empty());
+
+    // misspellings that can be split into words, given short enough
+    // or stemmed ones.
+    for &typo in ["mispelled", "recieving", "seperately", "accomodation", "readfiel",
+                  "retrun"].iter() {
+        let found = check_identifier(typo);
+        assert_eq!(found.len(), 1, "{}", typo);
+        assert_eq!(found[0].kind, Kind::Unknown);
+    }
+}
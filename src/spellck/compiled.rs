@@ -0,0 +1,244 @@
+//! A precompiled binary dictionary format, which can be loaded
+//! without parsing or allocating for each word.
+//!
+//! The format is:
+//!
+//! - the 8 byte magic number `SPELLCK` followed by a version byte,
+//! - the number of words `n`, as a little-endian `u32`,
+//! - `n + 1` little-endian `u32` offsets into the word data, the `i`th
+//!   word being the bytes between the `i`th and `i + 1`th offsets,
+//! - the word data: the UTF-8 words, sorted bytewise and without
+//!   duplicates, one after the other.
+//!
+//! A word with a required capitalisation, like `GitHub`, is stored
+//! as itself and also as a key `=github=GitHub` (starting with
+//! `dict::CASE_MARKER`), which sorts together with the keys of other
+//! such words and can be found from the lowercase form alone.
+//!
+//! Everything is stored in place, so once the file has been read
+//! the words are used where they are, with lookups done by binary
+//! search. Only the header is checked when it is loaded; each
+//! word's offsets are checked as it is read.
+
+use std::fs::File;
+use std::io;
+use std::io::prelude::*;
+use std::path::Path;
+use std::str;
+
+use dict::{self, Dictionary, CASE_MARKER};
+
+/// The magic number at the start of every compiled dictionary.
+pub static MAGIC: &[u8] = b"SPELLCK\x01";
+
+/// A dictionary in the compiled format.
+pub struct CompiledDict {
+    bytes: Vec<u8>,
+    count: usize,
+}
+
+impl CompiledDict {
+    /// Load a compiled dictionary from the file at `path`.
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<CompiledDict> {
+        let mut bytes = vec![];
+        File::open(path).and_then(|mut f| f.read_to_end(&mut bytes))?;
+        CompiledDict::from_bytes(bytes)
+    }
+
+    /// Interpret `bytes` as a compiled dictionary, checking that its
+    /// header is well-formed. Words with invalid offsets are treated
+    /// as missing.
+    pub fn from_bytes(bytes: Vec<u8>) -> io::Result<CompiledDict> {
+        fn invalid(msg: &str) -> io::Error {
+            io::Error::new(io::ErrorKind::InvalidData, msg)
+        }
+
+        if !bytes.starts_with(MAGIC) {
+            return Err(invalid("not a compiled spellck dictionary"))
+        }
+        if bytes.len() < header_len(0) {
+            return Err(invalid("compiled dictionary is truncated"))
+        }
+        let count = read_u32(&bytes, MAGIC.len()) as usize;
+        if bytes.len() < header_len(count) {
+            return Err(invalid("compiled dictionary is truncated"))
+        }
+
+        Ok(CompiledDict { bytes, count })
+    }
+
+    /// The number of words in the dictionary.
+    pub fn len(&self) -> usize {
+        self.count
+    }
+
+    /// Whether the dictionary has no words.
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    fn offset(&self, i: usize) -> usize {
+        read_u32(&self.bytes, MAGIC.len() + 4 + 4 * i) as usize
+    }
+
+    /// The `i`th word, or `None` if its offsets are out of range.
+    fn word(&self, i: usize) -> Option<&[u8]> {
+        let data = &self.bytes[header_len(self.count)..];
+        data.get(self.offset(i)..self.offset(i + 1))
+    }
+
+    /// The index of the first word that isn't less than `key`.
+    fn lower_bound(&self, key: &[u8]) -> usize {
+        let (mut lo, mut hi) = (0, self.count);
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            if self.word(mid).is_some_and(|w| w < key) {
+                lo = mid + 1
+            } else {
+                hi = mid
+            }
+        }
+        lo
+    }
+}
+
+/// The key under which the required capitalisation of the word with
+/// lowercase form `lower` is stored, without the capitalisation.
+fn case_key(lower: &str) -> String {
+    format!("{}{}{}", CASE_MARKER, lower, CASE_MARKER)
+}
+
+impl Dictionary for CompiledDict {
+    fn contains(&self, word: &str) -> bool {
+        let i = self.lower_bound(word.as_bytes());
+        i < self.count && self.word(i) == Some(word.as_bytes())
+    }
+
+    fn each_word(&self, f: &mut dyn FnMut(&str)) {
+        for i in 0..self.count {
+            match self.word(i).map(str::from_utf8) {
+                Some(Ok(w)) if !w.starts_with(CASE_MARKER) => f(w),
+                _ => {}
+            }
+        }
+    }
+
+    fn required_case(&self, lower: &str) -> Option<String> {
+        let key = case_key(lower);
+        let i = self.lower_bound(key.as_bytes());
+        if i == self.count { return None }
+        match self.word(i) {
+            Some(w) if w.starts_with(key.as_bytes()) => {
+                str::from_utf8(&w[key.len()..]).ok().map(|w| w.to_string())
+            }
+            _ => None
+        }
+    }
+
+    fn each_entry(&self, f: &mut dyn FnMut(&str)) {
+        for i in 0..self.count {
+            if let Some(Ok(w)) = self.word(i).map(str::from_utf8) {
+                if w.starts_with(CASE_MARKER) {
+                    // `=github=GitHub` -> `=GitHub`
+                    let key_len = w[1..].find(CASE_MARKER).map_or(0, |j| j + 1);
+                    f(&w[key_len..])
+                } else {
+                    f(w)
+                }
+            }
+        }
+    }
+}
+
+fn header_len(count: usize) -> usize {
+    MAGIC.len() + 4 + 4 * (count + 1)
+}
+
+fn read_u32(bytes: &[u8], at: usize) -> u32 {
+    bytes[at..at + 4].iter().rev().fold(0, |n, &b| (n << 8) | b as u32)
+}
+
+fn write_u32(out: &mut Vec<u8>, n: u32) {
+    for i in 0..4 {
+        out.push((n >> (8 * i)) as u8)
+    }
+}
+
+/// Compile a list of words into the binary format, normalizing them
+/// with `dict::normalize`. The words are entries as in a list of
+/// words, so can be marked with `dict::CASE_MARKER`.
+pub fn compile<I: IntoIterator<Item = String>>(entries: I) -> Vec<u8> {
+    let mut words = vec![];
+    for entry in entries {
+        let entry = dict::normalize(&entry);
+        if entry.starts_with(CASE_MARKER) && entry.len() > 1 {
+            let word = &entry[1..];
+            words.push(format!("{}{}", case_key(&dict::normalize(&word.to_lowercase())), word));
+            words.push(word.to_string());
+        } else {
+            words.push(entry);
+        }
+    }
+    words.sort();
+    words.dedup();
+
+    let mut out = MAGIC.to_vec();
+    write_u32(&mut out, words.len() as u32);
+    let mut offset = 0;
+    write_u32(&mut out, 0);
+    for w in words.iter() {
+        offset += w.len();
+        write_u32(&mut out, offset as u32);
+    }
+    for w in words.iter() {
+        out.extend(w.as_bytes().iter().cloned());
+    }
+    out
+}
+
+#[test]
+fn test_round_trip() {
+    let words = ["foo", "bar", "Baz", "bar", "ünïcödé"];
+    let bytes = compile(words.iter().map(|s| s.to_string()));
+    let d = CompiledDict::from_bytes(bytes).unwrap();
+
+    assert_eq!(d.len(), 4);
+    assert_eq!(d.required_case("baz"), None);
+    for w in words.iter() {
+        assert!(d.contains(w));
+    }
+    assert!(!d.contains("baz"));
+    assert!(!d.contains(""));
+
+    let mut listed = vec![];
+    d.each_word(&mut |w| listed.push(w.to_string()));
+    assert_eq!(listed, vec!["Baz", "bar", "foo", "ünïcödé"]);
+
+    assert!(CompiledDict::from_bytes(b"foo\nbar\n".to_vec()).is_err());
+
+    // offsets past the end of the data, or out of order.
+    let mut bytes = compile(words.iter().map(|s| s.to_string()));
+    let at = MAGIC.len() + 4 + 4 * 2;
+    bytes[at..at + 4].copy_from_slice(&[0xff; 4]);
+    let d = CompiledDict::from_bytes(bytes).unwrap();
+    assert!(!d.contains("bar"));
+    assert!(!d.contains("foo"));
+    let mut listed = vec![];
+    d.each_word(&mut |w| listed.push(w.to_string()));
+    assert_eq!(listed, vec!["Baz", "ünïcödé"]);
+
+    let entries = ["=GitHub", "git", "=Rust"];
+    let d = CompiledDict::from_bytes(compile(entries.iter().map(|s| s.to_string()))).unwrap();
+    assert!(d.contains("GitHub"));
+    assert!(!d.contains("github"));
+    assert_eq!(d.required_case("github"), Some("GitHub".to_string()));
+    assert_eq!(d.required_case("rust"), Some("Rust".to_string()));
+    assert_eq!(d.required_case("git"), None);
+
+    let mut listed = vec![];
+    d.each_word(&mut |w| listed.push(w.to_string()));
+    assert_eq!(listed, vec!["GitHub", "Rust", "git"]);
+    let mut listed = vec![];
+    d.each_entry(&mut |w| listed.push(w.to_string()));
+    assert_eq!(listed, vec!["=GitHub", "=Rust", "GitHub", "Rust", "git"]);
+}
@@ -0,0 +1,369 @@
+//! Configuration shared by the lint and the standalone binary.
+//!
+//! This is read from a `spellck.toml` file, or from the
+//! `[package.metadata.spellck]` table of `Cargo.toml`, e.g.
+//!
+//! ```toml
+//! # paths are relative to the file they're in.
+//! dictionaries = ["words.txt", "/usr/share/dict/words"]
+//! extra_words = ["rustc", "libsyntax"]
+//! # correct acronyms, in addition to the built-in ones.
+//! acronyms = ["GTK", "SDL"]
+//! # words (or whole identifiers) matching any of these are skipped;
+//! # `*` matches any sequence of characters and `?` any one.
+//! ignore = ["*_t", "x??"]
+//! # what to check: any of "docs", "mod", "fn", "struct", "field",
+//! # "enum", "variant", "trait", "method", "type", "static", "const".
+//! check = ["docs", "fn", "struct", "field"]
+//! # lists of words that aren't allowed (see `wordmap`), checked by
+//! # the `forbidden_words` lint.
+//! forbidden_words = ["forbidden.txt"]
+//! # lists of misspellings and their corrections (see `wordmap`), in
+//! # addition to the built-in one, checked by the `known_misspellings`
+//! # lint.
+//! known_misspellings = ["typos.txt"]
+//! # only report known misspellings and forbidden words, not every word
+//! # missing from the dictionaries.
+//! known_misspellings_only = true
+//! # words shorter than this are never reported.
+//! min_word_length = 3
+//! # report identifiers that are words run together, like `readfile`,
+//! # rather than accepting them.
+//! report_missing_separators = true
+//! # "human" or "json" (only used by the standalone binary).
+//! output_format = "json"
+//! ```
+
+use std::fs::File;
+use std::io::prelude::*;
+use std::path::{Path, PathBuf};
+
+use toml;
+
+use dict;
+
+/// The name of the dedicated configuration file.
+pub static CONFIG_FILE: &str = "spellck.toml";
+
+/// The things that can be checked.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum ItemKind {
+    /// Documentation, on anything.
+    Docs,
+    Mod,
+    Fn,
+    Struct,
+    Field,
+    Enum,
+    Variant,
+    Trait,
+    /// Methods and other items in traits and `impl`s.
+    Method,
+    Type,
+    Static,
+    Const,
+}
+
+impl ItemKind {
+    /// Every kind of item.
+    pub fn all() -> Vec<ItemKind> {
+        vec![ItemKind::Docs, ItemKind::Mod, ItemKind::Fn, ItemKind::Struct,
+             ItemKind::Field, ItemKind::Enum, ItemKind::Variant, ItemKind::Trait,
+             ItemKind::Method, ItemKind::Type, ItemKind::Static, ItemKind::Const]
+    }
+
+    /// The kind with the given name in the configuration.
+    pub fn from_name(name: &str) -> Option<ItemKind> {
+        ItemKind::all().into_iter().find(|k| k.name() == name)
+    }
+
+    /// The name of this kind in the configuration.
+    pub fn name(self) -> &'static str {
+        match self {
+            ItemKind::Docs => "docs",
+            ItemKind::Mod => "mod",
+            ItemKind::Fn => "fn",
+            ItemKind::Struct => "struct",
+            ItemKind::Field => "field",
+            ItemKind::Enum => "enum",
+            ItemKind::Variant => "variant",
+            ItemKind::Trait => "trait",
+            ItemKind::Method => "method",
+            ItemKind::Type => "type",
+            ItemKind::Static => "static",
+            ItemKind::Const => "const",
+        }
+    }
+}
+
+/// How the standalone binary prints misspellings.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum OutputFormat {
+    Human,
+    /// The JSON diagnostics of `rustc --error-format=json`.
+    Json,
+}
+
+impl OutputFormat {
+    /// The format with the given name, as used in the configuration
+    /// and on the command line.
+    pub fn from_name(name: &str) -> Option<OutputFormat> {
+        match name {
+            "human" => Some(OutputFormat::Human),
+            "json" => Some(OutputFormat::Json),
+            _ => None
+        }
+    }
+}
+
+/// The configuration of a project.
+#[derive(Clone, Debug)]
+pub struct Config {
+    /// Dictionaries to load, in addition to any given by the
+    /// environment or command line.
+    pub dictionaries: Vec<PathBuf>,
+    /// Extra correct words.
+    pub extra_words: Vec<String>,
+    /// Extra correct acronyms.
+    pub acronyms: Vec<String>,
+    /// Lists of forbidden words, and their replacements.
+    pub forbidden_words: Vec<PathBuf>,
+    /// Lists of misspellings, and their corrections.
+    pub known_misspellings: Vec<PathBuf>,
+    /// Whether only known misspellings (and forbidden words) should be
+    /// reported, rather than everything not in a dictionary.
+    pub known_misspellings_only: bool,
+    /// Patterns for words that shouldn't be checked.
+    pub ignore: Vec<String>,
+    /// The kinds of items to check.
+    pub check: Vec<ItemKind>,
+    /// Words with fewer characters than this are never misspelled.
+    pub min_word_length: usize,
+    /// Whether an identifier made of words without separators
+    /// (`readfile`) should be reported.
+    pub report_missing_separators: bool,
+    /// The output format of the standalone binary, if configured.
+    pub output_format: Option<OutputFormat>,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            dictionaries: vec![],
+            extra_words: vec![],
+            acronyms: vec![],
+            forbidden_words: vec![],
+            known_misspellings: vec![],
+            known_misspellings_only: false,
+            ignore: vec![],
+            check: ItemKind::all(),
+            min_word_length: 0,
+            report_missing_separators: false,
+            output_format: None,
+        }
+    }
+}
+
+impl Config {
+    /// Find and load the configuration for the crate with root file
+    /// `crate_root`: the closest `spellck.toml` or `Cargo.toml` with
+    /// a `[package.metadata.spellck]` table, searching up to the
+    /// workspace root as for `dict::project_word_files`. Returns
+    /// `Ok(None)` if there is no configuration.
+    pub fn find(crate_root: &Path) -> Result<Option<Config>, String> {
+        for dir in dict::project_dirs(crate_root) {
+            let path = dir.join(CONFIG_FILE);
+            if path.is_file() {
+                return Config::load(&path).map(Some)
+            }
+
+            let manifest = dir.join("Cargo.toml");
+            if manifest.is_file() {
+                let table = read_toml(&manifest)?;
+                let metadata = table.get("package")
+                    .and_then(|p| p.get("metadata"))
+                    .and_then(|m| m.get("spellck"));
+                if let Some(metadata) = metadata {
+                    return Config::from_toml(metadata, &dir)
+                        .map(Some)
+                        .map_err(|e| format!("{}: {}", manifest.display(), e))
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    /// Load the configuration file at `path`.
+    pub fn load(path: &Path) -> Result<Config, String> {
+        let value = read_toml(path)?;
+        let base = path.parent().unwrap_or(Path::new("."));
+        Config::from_toml(&value, base).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    /// Read the configuration from a table of TOML values, with
+    /// relative paths resolved against `base`.
+    pub fn from_toml(value: &toml::Value, base: &Path) -> Result<Config, String> {
+        let table = match value.as_table() {
+            Some(t) => t,
+            None => return Err("expected a table".to_string())
+        };
+
+        let mut config = Config::default();
+        for (key, value) in table.iter() {
+            match &**key {
+                "dictionaries" => {
+                    let paths = strings(key, value)?;
+                    config.dictionaries = paths.into_iter().map(|p| base.join(p)).collect();
+                }
+                "forbidden_words" => {
+                    let paths = strings(key, value)?;
+                    config.forbidden_words = paths.into_iter().map(|p| base.join(p)).collect();
+                }
+                "known_misspellings" => {
+                    let paths = strings(key, value)?;
+                    config.known_misspellings = paths.into_iter().map(|p| base.join(p)).collect();
+                }
+                "known_misspellings_only" => {
+                    config.known_misspellings_only = match value.as_bool() {
+                        Some(b) => b,
+                        None => return Err("`known_misspellings_only` should be a \
+                                            boolean".to_string())
+                    }
+                }
+                "extra_words" => {
+                    // a single string of words is allowed too, like
+                    // `spellck_extra_words`.
+                    let words = match value.as_str() {
+                        Some(s) => s.split_whitespace().map(|w| w.to_string()).collect(),
+                        None => strings(key, value)?
+                    };
+                    config.extra_words = words.iter().map(|w| dict::normalize(w)).collect();
+                }
+                "acronyms" => {
+                    let words = strings(key, value)?;
+                    config.acronyms = words.iter().map(|w| dict::normalize(w)).collect();
+                }
+                "ignore" => config.ignore = strings(key, value)?,
+                "check" => {
+                    let mut kinds = vec![];
+                    for name in strings(key, value)?.iter() {
+                        match ItemKind::from_name(name) {
+                            Some(k) => kinds.push(k),
+                            None => return Err(format!("unknown kind of item `{}` in `check`",
+                                                       name))
+                        }
+                    }
+                    config.check = kinds;
+                }
+                "min_word_length" => {
+                    config.min_word_length = match value.as_integer() {
+                        Some(n) if n >= 0 => n as usize,
+                        _ => return Err("`min_word_length` should be a non-negative \
+                                         integer".to_string())
+                    }
+                }
+                "report_missing_separators" => {
+                    config.report_missing_separators = match value.as_bool() {
+                        Some(b) => b,
+                        None => return Err("`report_missing_separators` should be a \
+                                            boolean".to_string())
+                    }
+                }
+                "output_format" => {
+                    let f = value.as_str().and_then(OutputFormat::from_name);
+                    match f {
+                        Some(f) => config.output_format = Some(f),
+                        None => return Err("`output_format` should be \"human\" or \
+                                            \"json\"".to_string())
+                    }
+                }
+                _ => return Err(format!("unknown key `{}`", key))
+            }
+        }
+        Ok(config)
+    }
+
+    /// Whether the given kind of item should be checked.
+    pub fn checks(&self, kind: ItemKind) -> bool {
+        self.check.contains(&kind)
+    }
+
+    /// Whether `word` should be skipped entirely, because it is too
+    /// short or matches an `ignore` pattern.
+    pub fn ignores(&self, word: &str) -> bool {
+        word.chars().count() < self.min_word_length ||
+            self.ignore.iter().any(|p| glob_matches(p, word))
+    }
+}
+
+/// Read and parse the TOML file at `path`.
+pub fn read_toml(path: &Path) -> Result<toml::Value, String> {
+    let mut s = String::new();
+    File::open(path).and_then(|mut f| f.read_to_string(&mut s))
+        .map_err(|e| format!("error reading {}: {}", path.display(), e))?;
+    s.parse::<toml::Value>().map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+/// The array of strings `value`, which is the value of `key`.
+pub fn strings(key: &str, value: &toml::Value) -> Result<Vec<String>, String> {
+    let err = || format!("`{}` should be an array of strings", key);
+    let array = value.as_array().ok_or_else(&err)?;
+    array.iter()
+        .map(|v| v.as_str().map(|s| s.to_string()).ok_or_else(&err))
+        .collect()
+}
+
+/// Whether `word` matches the glob `pattern`, where `*` matches any
+/// sequence of characters (including none), `?` any single character,
+/// and everything else itself.
+pub fn glob_matches(pattern: &str, word: &str) -> bool {
+    let p = pattern.chars().collect::<Vec<_>>();
+    let w = word.chars().collect::<Vec<_>>();
+
+    // the usual backtracking algorithm: remember the last `*`, and
+    // where in the word it started matching, so that it can be
+    // extended by one character if a later part fails.
+    let (mut pi, mut wi) = (0, 0);
+    let mut star = None;
+    while wi < w.len() {
+        if pi < p.len() && (p[pi] == '?' || p[pi] == w[wi]) {
+            pi += 1;
+            wi += 1;
+        } else if pi < p.len() && p[pi] == '*' {
+            star = Some((pi, wi));
+            pi += 1;
+        } else if let Some((spi, swi)) = star {
+            pi = spi + 1;
+            wi = swi + 1;
+            star = Some((spi, swi + 1));
+        } else {
+            return false
+        }
+    }
+    p[pi..].iter().all(|&c| c == '*')
+}
+
+#[test]
+fn test_config() {
+    assert!(glob_matches("*_t", "size_t"));
+    assert!(glob_matches("x??", "xyz"));
+    assert!(!glob_matches("x??", "xy"));
+    assert!(glob_matches("a*b*c", "aXbYbc"));
+    assert!(!glob_matches("a*b", "ab_"));
+
+    let value = "dictionaries = [\"words.txt\"]\n\
+                 extra_words = \"foo bar\"\n\
+                 check = [\"docs\", \"fn\"]\n\
+                 min_word_length = 3\n\
+                 known_misspellings_only = true\n".parse::<toml::Value>().unwrap();
+    let config = Config::from_toml(&value, Path::new("/project")).unwrap();
+    assert_eq!(config.dictionaries, vec![PathBuf::from("/project/words.txt")]);
+    assert_eq!(config.extra_words, vec!["foo", "bar"]);
+    assert_eq!(config.check, vec![ItemKind::Docs, ItemKind::Fn]);
+    assert!(config.ignores("ab"));
+    assert!(!config.ignores("abc"));
+    assert!(config.known_misspellings_only);
+
+    let bad = "check = [\"nothing\"]".parse::<toml::Value>().unwrap();
+    assert!(Config::from_toml(&bad, Path::new(".")).is_err());
+}
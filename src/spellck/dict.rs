@@ -0,0 +1,345 @@
+//! Dictionaries, the sources of correctly spelled words, and loading
+//! them from files.
+
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::fs::File;
+use std::io;
+use std::io::prelude::*;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+
+use unicode_normalization::UnicodeNormalization;
+
+use hunspell;
+use compiled::{self, CompiledDict};
+
+/// A source of correctly spelled words.
+///
+/// The checker queries a dictionary with each word it finds (along
+/// with the lowercased and stemmed forms of alphabetic words), so an
+/// implementation only needs to answer whether one exact word is
+/// known: this can be a list of words, but can equally be a pattern,
+/// or a closure encoding a project's naming scheme. The words are
+/// always in Unicode Normalization Form C (see `normalize`), as are
+/// the words of every dictionary loaded by spellck.
+pub trait Dictionary {
+    /// Whether `word` is spelled correctly.
+    fn contains(&self, word: &str) -> bool;
+
+    /// Call `f` with every word in the dictionary, to find
+    /// suggestions for misspelled words. Dictionaries that can't
+    /// list their words (like a pattern) can leave this as the
+    /// default, which lists nothing.
+    fn each_word(&self, _f: &mut dyn FnMut(&str)) {}
+
+    /// The capitalisation that the word with the (normalized)
+    /// lowercase form `lower` must always be written with in prose,
+    /// if the dictionary has one, e.g. `GitHub` for `github`.
+    fn required_case(&self, _lower: &str) -> Option<String> {
+        None
+    }
+
+    /// Call `f` with every entry of the dictionary as it would be
+    /// written in a list of words, i.e. with `CASE_MARKER` in front
+    /// of words with a required capitalisation. This is for
+    /// converting between formats, and defaults to `each_word`.
+    fn each_entry(&self, f: &mut dyn FnMut(&str)) {
+        self.each_word(f)
+    }
+}
+
+/// The marker at the start of an entry in a list of words, like
+/// `=GitHub`, which means that the word must be capitalised exactly
+/// that way in prose: `github` and `Github` are reported as having the
+/// wrong capitalisation, rather than being accepted.
+pub static CASE_MARKER: char = '=';
+
+/// A list of words, as read from a file.
+#[derive(Default)]
+pub struct WordList {
+    words: HashSet<String>,
+    /// The lowercase forms of the words with a required
+    /// capitalisation, and that capitalisation.
+    cased: HashMap<String, String>,
+}
+
+impl WordList {
+    /// Create an empty list.
+    pub fn new() -> WordList {
+        WordList::default()
+    }
+}
+
+/// Entries are added as they are written in a file, so `=GitHub` adds
+/// `GitHub`, with that capitalisation required.
+impl Extend<String> for WordList {
+    fn extend<I: IntoIterator<Item = String>>(&mut self, entries: I) {
+        for entry in entries {
+            if entry.starts_with(CASE_MARKER) && entry.len() > 1 {
+                let word = entry[1..].to_string();
+                self.cased.insert(normalize(&word.to_lowercase()), word.clone());
+                self.words.insert(word);
+            } else {
+                self.words.insert(entry);
+            }
+        }
+    }
+}
+
+impl Dictionary for WordList {
+    fn contains(&self, word: &str) -> bool {
+        self.words.contains(word)
+    }
+    fn each_word(&self, f: &mut dyn FnMut(&str)) {
+        for w in self.words.iter() { f(w) }
+    }
+    fn required_case(&self, lower: &str) -> Option<String> {
+        self.cased.get(lower).cloned()
+    }
+    fn each_entry(&self, f: &mut dyn FnMut(&str)) {
+        for w in self.words.iter() {
+            if self.cased.get(&normalize(&w.to_lowercase())) == Some(w) {
+                f(&format!("{}{}", CASE_MARKER, w))
+            } else {
+                f(w)
+            }
+        }
+    }
+}
+
+impl Dictionary for HashSet<String> {
+    fn contains(&self, word: &str) -> bool {
+        HashSet::contains(self, word)
+    }
+    fn each_word(&self, f: &mut dyn FnMut(&str)) {
+        for w in self.iter() { f(w) }
+    }
+}
+
+impl<F: Fn(&str) -> bool> Dictionary for F {
+    fn contains(&self, word: &str) -> bool {
+        self(word)
+    }
+}
+
+/// A stack of dictionaries, which contains a word if any of its
+/// members do.
+#[derive(Default)]
+pub struct Chain<'a> {
+    dicts: Vec<Box<dyn Dictionary + 'a>>,
+}
+
+impl<'a> Chain<'a> {
+    /// Create an empty chain, which contains no words.
+    pub fn new() -> Chain<'a> {
+        Chain { dicts: vec![] }
+    }
+
+    /// Add a dictionary to the chain.
+    pub fn push<D: Dictionary + 'a>(&mut self, dict: D) {
+        self.dicts.push(Box::new(dict))
+    }
+
+    /// Add an already boxed dictionary to the chain.
+    pub fn push_boxed(&mut self, dict: Box<dyn Dictionary + 'a>) {
+        self.dicts.push(dict)
+    }
+}
+
+impl<'a> Dictionary for Chain<'a> {
+    fn contains(&self, word: &str) -> bool {
+        self.dicts.iter().any(|d| d.contains(word))
+    }
+    fn each_word(&self, f: &mut dyn FnMut(&str)) {
+        for d in self.dicts.iter() { d.each_word(f) }
+    }
+    fn required_case(&self, lower: &str) -> Option<String> {
+        self.dicts.iter().filter_map(|d| d.required_case(lower)).next()
+    }
+    fn each_entry(&self, f: &mut dyn FnMut(&str)) {
+        for d in self.dicts.iter() { d.each_entry(f) }
+    }
+}
+
+impl<'a, 'b> Dictionary for &'b Chain<'a> {
+    fn contains(&self, word: &str) -> bool {
+        (**self).contains(word)
+    }
+    fn each_word(&self, f: &mut dyn FnMut(&str)) {
+        (**self).each_word(f)
+    }
+    fn required_case(&self, lower: &str) -> Option<String> {
+        (**self).required_case(lower)
+    }
+    fn each_entry(&self, f: &mut dyn FnMut(&str)) {
+        (**self).each_entry(f)
+    }
+}
+
+/// The name of the files listing a project's own words.
+pub static PROJECT_WORDS_FILE: &str = ".spellck-words";
+
+/// Find the project word files (`.spellck-words`) that apply to the
+/// crate with root file `crate_root`, in any of the directories of
+/// `project_dirs`. The files are returned innermost first, and are all
+/// meant to be used together.
+pub fn project_word_files(crate_root: &Path) -> Vec<PathBuf> {
+    project_dirs(crate_root).into_iter()
+        .map(|d| d.join(PROJECT_WORDS_FILE))
+        .filter(|f| f.is_file())
+        .collect()
+}
+
+/// The directories that can hold files configuring the crate with
+/// root file `crate_root`, innermost first.
+///
+/// These start at the directory containing `crate_root` and walk up to
+/// the root of the Cargo workspace, or to the package root if the
+/// package isn't part of a workspace, so a workspace and each of its
+/// members can each have their own configuration.
+pub fn project_dirs(crate_root: &Path) -> Vec<PathBuf> {
+    // the parents of a relative path stop at the current directory.
+    let crate_root = match env::current_dir() {
+        Ok(cwd) => cwd.join(crate_root),
+        Err(_) => crate_root.to_path_buf()
+    };
+    let start = match crate_root.parent() {
+        Some(p) => p,
+        None => return vec![]
+    };
+
+    let mut dirs = vec![];
+    let mut package_dirs = 0;
+    let mut workspace_dirs = None;
+    let mut dir = Some(start);
+    while let Some(d) = dir {
+        dirs.push(d.to_path_buf());
+        let manifest = d.join("Cargo.toml");
+        if manifest.is_file() {
+            if package_dirs == 0 { package_dirs = dirs.len() }
+            if is_workspace_manifest(&manifest) {
+                workspace_dirs = Some(dirs.len());
+                break
+            }
+        }
+        dir = d.parent();
+    }
+
+    let levels = match workspace_dirs {
+        Some(n) => n,
+        None if package_dirs > 0 => package_dirs,
+        // not in a Cargo project at all, so there's no good place to
+        // stop.
+        None => 1,
+    };
+    dirs.truncate(levels);
+    dirs
+}
+
+fn is_workspace_manifest(path: &Path) -> bool {
+    let mut s = String::new();
+    match File::open(path).and_then(|mut f| f.read_to_string(&mut s)) {
+        Ok(_) => s.lines().any(|l| {
+            let l = l.trim();
+            l == "[workspace]" || l.starts_with("[workspace.")
+        }),
+        Err(_) => false
+    }
+}
+
+/// Load the dictionary at `path`, in any of the supported formats.
+///
+/// As well as the formats read by `load_into`, this understands
+/// dictionaries compiled by `spellck dict compile` (recognised by
+/// their contents, rather than name), which are used as is rather
+/// than being loaded into a `WordList`.
+pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Box<dyn Dictionary>> {
+    let path = path.as_ref();
+    if is_hunspell(path) {
+        let mut words = WordList::new();
+        load_into(path, &mut words)?;
+        return Ok(Box::new(words))
+    }
+
+    let mut bytes = vec![];
+    File::open(path).and_then(|mut f| f.read_to_end(&mut bytes))?;
+    if bytes.starts_with(compiled::MAGIC) {
+        let d = CompiledDict::from_bytes(bytes)?;
+        Ok(Box::new(d))
+    } else {
+        let mut words = WordList::new();
+        read_word_list(&bytes[..], &mut words)?;
+        Ok(Box::new(words))
+    }
+}
+
+/// Load the words of the dictionary at `path` into `words`.
+///
+/// A file with a `.dic` extension is read as a Hunspell dictionary,
+/// with the affix rules taken from the `.aff` file next to it. Any
+/// other file is a plain list of words, one per line, each possibly
+/// marked with `CASE_MARKER`.
+pub fn load_into<P: AsRef<Path>, E: Extend<String>>(path: P, words: &mut E) -> io::Result<()> {
+    let path = path.as_ref();
+    if is_hunspell(path) {
+        let w = hunspell::load(path, path.with_extension("aff"))?;
+        words.extend(w.iter().map(|w| normalize(w)));
+        return Ok(())
+    }
+
+    read_word_list(BufReader::new(File::open(path)?), words)
+}
+
+/// Put `word` into Unicode Normalization Form C, so that e.g. a `é`
+/// written as `e` and a combining accent matches one written as a
+/// single character.
+pub fn normalize(word: &str) -> String {
+    word.nfc().collect()
+}
+
+fn is_hunspell(path: &Path) -> bool {
+    path.extension().is_some_and(|e| e == "dic")
+}
+
+fn read_word_list<R: BufRead, E: Extend<String>>(rdr: R, words: &mut E) -> io::Result<()> {
+    let lines = rdr.lines().map(|l| l.map(|s| normalize(s.trim())));
+    let w = lines.collect::<Result<Vec<String>, _>>()?;
+    words.extend(w.into_iter().filter(|s| !s.is_empty()));
+    Ok(())
+}
+
+#[test]
+fn test_normalize() {
+    let mut words = HashSet::new();
+    read_word_list(&b"cafe\xcc\x81\n  Zo\xc3\xab \n\n"[..], &mut words).unwrap();
+    assert!(words.contains("caf\u{e9}"));
+    assert!(words.contains("Zo\u{eb}"));
+    assert_eq!(normalize("Zoe\u{308}"), "Zo\u{eb}");
+}
+
+#[test]
+fn test_word_list() {
+    let mut words = WordList::new();
+    read_word_list(&b"=GitHub\nrust\n=Rust\n"[..], &mut words).unwrap();
+    assert!(words.contains("GitHub"));
+    assert!(!words.contains("github"));
+    assert!(words.contains("rust"));
+    assert_eq!(words.required_case("github"), Some("GitHub".to_string()));
+    assert_eq!(words.required_case("rust"), Some("Rust".to_string()));
+    assert_eq!(words.required_case("git"), None);
+
+    let mut entries = vec![];
+    words.each_entry(&mut |e| entries.push(e.to_string()));
+    entries.sort();
+    assert_eq!(entries, vec!["=GitHub", "=Rust", "rust"]);
+}
+
+#[test]
+fn test_project_dirs() {
+    // tests are run from the package root, which isn't in a workspace.
+    let cwd = env::current_dir().unwrap();
+    assert_eq!(project_dirs(Path::new("src/spellck/dict.rs")),
+               vec![cwd.join("src/spellck"), cwd.join("src"), cwd.clone()]);
+    assert_eq!(project_dirs(&cwd.join("Cargo.toml")), vec![cwd]);
+}
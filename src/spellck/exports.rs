@@ -0,0 +1,418 @@
+//! Working out which items of a crate read by `source` are part of
+//! its public API, the way rustdoc does.
+//!
+//! An item is exported if it is `pub` in an exported module (starting
+//! at the crate root), or if it is re-exported by a `pub use` in an
+//! exported module, whether by name or by a glob, from anywhere in the
+//! crate. A re-exported module, even a private one, exports its `pub`
+//! items in turn. Restricted visibilities like `pub(crate)` are
+//! private. Items are identified by the position of their name, so
+//! this is the equivalent of the compiler's `ExportedItems`.
+
+use std::collections::{HashMap, HashSet};
+
+use syn;
+
+use source::{self, Crate};
+
+/// Something a path can refer to.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+enum Def {
+    /// A module, by its index in `Resolver::modules`.
+    Mod(usize),
+    /// An enum, by the position of its name; its variants can be
+    /// named through it.
+    Enum(usize),
+    /// Any other item, including an enum variant, by the position of
+    /// its name.
+    Item(usize),
+}
+
+/// A `use` declaration, with any `{...}` groups expanded.
+struct Import {
+    /// The path being imported, starting with `{{root}}` for `::`.
+    path: Vec<String>,
+    /// The name it is imported as, or `None` for a glob.
+    name: Option<String>,
+    public: bool,
+}
+
+/// An `impl` block, by the position of the `impl` keyword.
+struct Impl {
+    pos: usize,
+    /// The path of the type, if it is one.
+    self_ty: Option<Vec<String>>,
+    /// The path of the trait, for a trait impl.
+    trait_: Option<Vec<String>>,
+}
+
+#[derive(Default)]
+struct Module {
+    /// The position of the module's name, or `None` for the root.
+    pos: Option<usize>,
+    parent: Option<usize>,
+    /// The items declared in the module, by name, and whether each is
+    /// `pub`.
+    items: HashMap<String, Vec<(Def, bool)>>,
+    imports: Vec<Import>,
+    impls: Vec<Impl>,
+}
+
+/// The names and definitions of a crate, for resolving paths.
+struct Resolver {
+    modules: Vec<Module>,
+    /// The variants of each enum, by the position of its name.
+    variants: HashMap<usize, Vec<(String, usize)>>,
+}
+
+/// The items of a crate that are part of its public API.
+pub struct Exports {
+    /// The positions of the names of the exported items.
+    items: HashSet<usize>,
+    /// The positions of the `impl` keywords of the `impl`s of exported
+    /// types or traits.
+    impls: HashSet<usize>,
+}
+
+impl Exports {
+    /// Work out what `krate` exports.
+    pub fn new(krate: &Crate) -> Exports {
+        let mut resolver = Resolver { modules: vec![Module::default()], variants: HashMap::new() };
+        resolver.collect(krate, &krate.file.items, krate.root_file(), 0);
+
+        let mut marker = Marker {
+            resolver: &resolver,
+            modules: HashSet::new(),
+            globs: HashSet::new(),
+            items: HashSet::new(),
+        };
+        marker.mark(Def::Mod(0));
+        let items = marker.items;
+
+        let mut impls = HashSet::new();
+        for (i, module) in resolver.modules.iter().enumerate() {
+            for imp in module.impls.iter() {
+                let exported = imp.self_ty.iter().chain(imp.trait_.iter()).any(|path| {
+                    resolver.resolve(i, path).into_iter().any(|d| match d {
+                        Def::Mod(_) => false,
+                        Def::Enum(pos) | Def::Item(pos) => items.contains(&pos)
+                    })
+                });
+                if exported {
+                    impls.insert(imp.pos);
+                }
+            }
+        }
+
+        Exports { items, impls }
+    }
+
+    /// Whether the item whose name is at position `pos` is exported.
+    pub fn contains(&self, pos: usize) -> bool {
+        self.items.contains(&pos)
+    }
+
+    /// Whether the `impl` whose keyword is at `pos` is for an
+    /// exported type or trait.
+    pub fn contains_impl(&self, pos: usize) -> bool {
+        self.impls.contains(&pos)
+    }
+}
+
+/// The segments of a path, with `{{root}}` for a leading `::`.
+fn path_segments(path: &syn::Path) -> Vec<String> {
+    let root = path.leading_colon.map(|_| "{{root}}".to_string());
+    root.into_iter().chain(path.segments.iter().map(|s| source::unraw(&s.ident))).collect()
+}
+
+/// Expand a `use` tree into the imports it makes, with the path so far
+/// being `prefix`.
+fn flatten_use(tree: &syn::UseTree, prefix: &mut Vec<String>, public: bool,
+               imports: &mut Vec<Import>) {
+    match *tree {
+        syn::UseTree::Path(ref p) => {
+            prefix.push(source::unraw(&p.ident));
+            flatten_use(&p.tree, prefix, public, imports);
+            prefix.pop();
+        }
+        syn::UseTree::Name(ref n) => {
+            let name = source::unraw(&n.ident);
+            // `use foo::{self}` imports `foo`.
+            let (path, name) = if name == "self" {
+                (prefix.clone(), prefix.last().cloned().unwrap_or(name))
+            } else {
+                let mut path = prefix.clone();
+                path.push(name.clone());
+                (path, name)
+            };
+            imports.push(Import { path, name: Some(name), public })
+        }
+        syn::UseTree::Rename(ref r) => {
+            let mut path = prefix.clone();
+            let name = source::unraw(&r.ident);
+            if name != "self" { path.push(name) }
+            imports.push(Import { path, name: Some(source::unraw(&r.rename)),
+                                  public })
+        }
+        syn::UseTree::Glob(_) => {
+            imports.push(Import { path: prefix.clone(), name: None, public })
+        }
+        syn::UseTree::Group(ref g) => {
+            for tree in g.items.iter() {
+                flatten_use(tree, prefix, public, imports)
+            }
+        }
+    }
+}
+
+impl Resolver {
+    fn add(&mut self, module: usize, ident: &syn::Ident, def: Def, public: bool) {
+        self.modules[module].items.entry(source::unraw(ident)).or_insert(vec![])
+            .push((def, public))
+    }
+
+    /// Record the names declared by `items`, which are in module
+    /// `module` and file `file`.
+    fn collect(&mut self, krate: &Crate, items: &[syn::Item], file: usize, module: usize) {
+        let pos = |ident: &syn::Ident| krate.span(file, ident.span()).lo;
+        for item in items.iter() {
+            let (ident, vis) = match *item {
+                syn::Item::Const(ref i) => (&i.ident, &i.vis),
+                syn::Item::Static(ref i) => (&i.ident, &i.vis),
+                syn::Item::Fn(ref i) => (&i.sig.ident, &i.vis),
+                syn::Item::Type(ref i) => (&i.ident, &i.vis),
+                syn::Item::Struct(ref i) => (&i.ident, &i.vis),
+                syn::Item::Union(ref i) => (&i.ident, &i.vis),
+                syn::Item::Trait(ref i) => (&i.ident, &i.vis),
+                syn::Item::TraitAlias(ref i) => (&i.ident, &i.vis),
+                syn::Item::Enum(ref i) => {
+                    let enum_pos = pos(&i.ident);
+                    let variants = i.variants.iter()
+                        .map(|v| (source::unraw(&v.ident), pos(&v.ident))).collect();
+                    self.variants.insert(enum_pos, variants);
+                    self.add(module, &i.ident, Def::Enum(enum_pos), source::is_public(&i.vis));
+                    continue
+                }
+                syn::Item::Mod(ref i) => {
+                    let index = self.modules.len();
+                    self.modules.push(Module {
+                        pos: Some(pos(&i.ident)),
+                        parent: Some(module),
+                        ..Module::default()
+                    });
+                    self.add(module, &i.ident, Def::Mod(index), source::is_public(&i.vis));
+
+                    let file = krate.mod_file(file, i).map_or(file, |(f, _)| f);
+                    if let Some((_, ref items)) = i.content {
+                        self.collect(krate, items, file, index);
+                    }
+                    continue
+                }
+                syn::Item::ForeignMod(ref i) => {
+                    for item in i.items.iter() {
+                        let (ident, vis) = match *item {
+                            syn::ForeignItem::Fn(ref f) => (&f.sig.ident, &f.vis),
+                            syn::ForeignItem::Static(ref s) => (&s.ident, &s.vis),
+                            syn::ForeignItem::Type(ref t) => (&t.ident, &t.vis),
+                            _ => continue
+                        };
+                        self.add(module, ident, Def::Item(pos(ident)), source::is_public(vis));
+                    }
+                    continue
+                }
+                syn::Item::Use(ref i) => {
+                    let mut prefix = i.leading_colon.map(|_| "{{root}}".to_string())
+                        .into_iter().collect();
+                    flatten_use(&i.tree, &mut prefix, source::is_public(&i.vis),
+                                &mut self.modules[module].imports);
+                    continue
+                }
+                syn::Item::Impl(ref i) => {
+                    let self_ty = match *i.self_ty {
+                        syn::Type::Path(ref p) => Some(path_segments(&p.path)),
+                        _ => None
+                    };
+                    self.modules[module].impls.push(Impl {
+                        pos: krate.span(file, i.impl_token.span).lo,
+                        self_ty,
+                        trait_: i.trait_.as_ref().map(|(_, path, _)| path_segments(path)),
+                    });
+                    continue
+                }
+                _ => continue
+            };
+            self.add(module, ident, Def::Item(pos(ident)), source::is_public(vis));
+        }
+    }
+
+    /// What `path` refers to in module `module`. Paths that don't
+    /// start with `crate`, `self`, `super` or `::` are looked up in
+    /// the module, and then the crate root (as in the 2015 edition);
+    /// anything outside the crate refers to nothing.
+    fn resolve(&self, module: usize, path: &[String]) -> Vec<Def> {
+        self.resolve_in(module, path, &mut HashSet::new())
+    }
+
+    fn resolve_in(&self, module: usize, path: &[String],
+                  visiting: &mut HashSet<(usize, String)>) -> Vec<Def> {
+        let (first, rest) = match path.split_first() {
+            Some(p) => p,
+            None => return vec![]
+        };
+        let mut defs = match &**first {
+            "crate" | "{{root}}" => vec![Def::Mod(0)],
+            "self" => vec![Def::Mod(module)],
+            "super" => self.modules[module].parent.map(Def::Mod).into_iter().collect(),
+            name => {
+                let defs = self.lookup(module, name, visiting);
+                if defs.is_empty() && module != 0 {
+                    self.lookup(0, name, visiting)
+                } else {
+                    defs
+                }
+            }
+        };
+
+        for segment in rest.iter() {
+            let mut next = vec![];
+            for def in defs.into_iter() {
+                match def {
+                    Def::Mod(m) => match &**segment {
+                        "self" => next.push(def),
+                        "super" => next.extend(self.modules[m].parent.map(Def::Mod)),
+                        name => next.extend(self.lookup(m, name, visiting))
+                    },
+                    Def::Enum(e) => next.extend(self.variant(e, segment)),
+                    Def::Item(_) => {}
+                }
+            }
+            defs = next;
+        }
+        defs
+    }
+
+    /// What `name` refers to in module `module`, whether declared
+    /// there or imported.
+    fn lookup(&self, module: usize, name: &str,
+              visiting: &mut HashSet<(usize, String)>) -> Vec<Def> {
+        // imports can refer to each other in cycles, via globs.
+        let key = (module, name.to_string());
+        if !visiting.insert(key.clone()) { return vec![] }
+
+        let m = &self.modules[module];
+        let mut defs = m.items.get(name).map_or(vec![], |d| d.iter().map(|&(d, _)| d).collect());
+        for import in m.imports.iter() {
+            match import.name {
+                Some(ref n) if n == name => {
+                    defs.extend(self.resolve_in(module, &import.path, visiting))
+                }
+                Some(_) => {}
+                None => for target in self.resolve_in(module, &import.path, visiting) {
+                    match target {
+                        Def::Mod(t) => defs.extend(self.lookup(t, name, visiting)),
+                        Def::Enum(e) => defs.extend(self.variant(e, name)),
+                        Def::Item(_) => {}
+                    }
+                }
+            }
+        }
+
+        visiting.remove(&key);
+        defs
+    }
+
+    /// The variant of the enum at `e` called `name`, if any.
+    fn variant(&self, e: usize, name: &str) -> Option<Def> {
+        self.variants.get(&e)
+            .and_then(|vs| vs.iter().find(|&(n, _)| n == name))
+            .map(|&(_, pos)| Def::Item(pos))
+    }
+}
+
+/// Marks everything reachable from the crate root.
+struct Marker<'a> {
+    resolver: &'a Resolver,
+    /// The exported modules.
+    modules: HashSet<usize>,
+    /// The modules and enums whose public names have been exported by
+    /// a glob.
+    globs: HashSet<Def>,
+    /// The positions of the names of the exported items.
+    items: HashSet<usize>,
+}
+
+impl<'a> Marker<'a> {
+    fn mark(&mut self, def: Def) {
+        match def {
+            Def::Mod(m) => {
+                if !self.modules.insert(m) { return }
+                self.items.extend(self.resolver.modules[m].pos);
+                self.mark_public(m);
+            }
+            Def::Enum(pos) | Def::Item(pos) => {
+                self.items.insert(pos);
+            }
+        }
+    }
+
+    /// Mark the `pub` items and `pub use` re-exports of module `m`.
+    fn mark_public(&mut self, m: usize) {
+        let resolver = self.resolver;
+        let module = &resolver.modules[m];
+        for defs in module.items.values() {
+            for &(def, public) in defs.iter() {
+                if public { self.mark(def) }
+            }
+        }
+
+        for import in module.imports.iter().filter(|i| i.public) {
+            for def in resolver.resolve(m, &import.path) {
+                if import.name.is_some() {
+                    self.mark(def)
+                } else if self.globs.insert(def) {
+                    match def {
+                        Def::Mod(t) => self.mark_public(t),
+                        Def::Enum(e) => for &(_, pos) in resolver.variants[&e].iter() {
+                            self.items.insert(pos);
+                        },
+                        Def::Item(_) => {}
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_exports() {
+    use std::path::Path;
+
+    let src = "pub fn a() {}\n\
+               fn b() {}\n\
+               mod private { pub fn c() {} pub fn d() {} pub mod m { pub fn e() {} } }\n\
+               pub use private::c;\n\
+               pub use self::private::m as n;\n\
+               pub mod public { pub(crate) fn f() {} pub use super::globbed::*; }\n\
+               mod globbed { pub struct G; fn h() {} }\n\
+               enum E { V, W }\n\
+               pub use E::V;\n\
+               struct S;\n\
+               impl S { pub fn i() {} }\n\
+               pub struct T;\n\
+               impl T { pub fn j() {} }\n";
+    let krate = Crate::from_source(Path::new("lib.rs"), src.to_string()).unwrap();
+    let exports = Exports::new(&krate);
+    // the position of the name at the end of `item`.
+    let name = |item: &str| src.find(item).unwrap() + item.rfind(' ').unwrap() + 1;
+
+    let exported = ["fn a", "fn c", "mod m", "fn e", "mod public", "struct G", "{ V"];
+    let private = ["fn b", "fn d", "mod private", "fn f", "fn h", "enum E", ", W", "struct S"];
+    for item in exported.iter() {
+        assert!(exports.contains(name(item)), "`{}` should be exported", item);
+    }
+    for item in private.iter() {
+        assert!(!exports.contains(name(item)), "`{}` shouldn't be exported", item);
+    }
+    assert!(!exports.contains_impl(src.find("impl S").unwrap()));
+    assert!(exports.contains_impl(src.find("impl T").unwrap()));
+}
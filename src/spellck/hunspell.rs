@@ -0,0 +1,381 @@
+//! Loading Hunspell dictionaries.
+//!
+//! A Hunspell dictionary is a pair of files: the `.dic` file lists
+//! stems, each with a set of flags, and the `.aff` file describes the
+//! prefixes and suffixes that each flag allows to be attached to the
+//! stem. This expands every stem with all of its affixes, to give the
+//! plain list of words that the rest of the checker works with.
+
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io;
+use std::io::prelude::*;
+use std::path::Path;
+
+/// Load the Hunspell dictionary made up of `dic` and `aff`, returning
+/// every word it accepts.
+pub fn load<P: AsRef<Path>, Q: AsRef<Path>>(dic: P, aff: Q) -> io::Result<Vec<String>> {
+    let aff = read_bytes(aff.as_ref())?;
+    let dic = read_bytes(dic.as_ref())?;
+
+    // the encoding is declared in the affix file, but that line is
+    // always ASCII, so it can be found before knowing the encoding.
+    let latin1 = String::from_utf8_lossy(&aff).lines().any(|l| {
+        let mut parts = l.split_whitespace();
+        parts.next() == Some("SET") &&
+            parts.next().is_some_and(|e| e.starts_with("ISO8859-1") ||
+                                           e.starts_with("ISO-8859-1"))
+    });
+    let decode = |bytes: Vec<u8>| -> io::Result<String> {
+        if latin1 {
+            Ok(bytes.into_iter().map(|b| b as char).collect())
+        } else {
+            String::from_utf8(bytes).map_err(|_| {
+                io::Error::new(io::ErrorKind::InvalidData,
+                               "Hunspell dictionary is not valid UTF-8")
+            })
+        }
+    };
+
+    let aff = decode(aff)?;
+    let dic = decode(dic)?;
+    Ok(expand(&dic, &aff))
+}
+
+fn read_bytes(p: &Path) -> io::Result<Vec<u8>> {
+    let mut v = vec![];
+    File::open(p).and_then(|mut f| f.read_to_end(&mut v))?;
+    Ok(v)
+}
+
+/// Expand the contents of a `.dic` file according to the rules of the
+/// contents of an `.aff` file.
+pub fn expand(dic: &str, aff: &str) -> Vec<String> {
+    let aff = Affixes::parse(aff);
+    let mut words = vec![];
+
+    // the first line is an (approximate) count of the entries.
+    for line in dic.lines().skip(1) {
+        let (stem, flags) = match split_entry(line) {
+            Some(x) => x,
+            None => continue
+        };
+        let flags = aff.entry_flags(&flags);
+        let has = |flag: &Option<Flag>| flag.as_ref().is_some_and(|f| flags.contains(f));
+        if has(&aff.forbidden) { continue }
+
+        if !has(&aff.need_affix) && !has(&aff.only_in_compound) {
+            words.push(stem.clone());
+        }
+
+        let prefixes = aff.prefixes.iter().filter(|a| flags.contains(&a.flag)).collect::<Vec<_>>();
+        let suffixes = aff.suffixes.iter().filter(|a| flags.contains(&a.flag)).collect::<Vec<_>>();
+
+        for pfx in prefixes.iter() {
+            for rule in pfx.rules.iter() {
+                if let Some(w) = rule.apply_prefix(&stem) {
+                    words.push(w)
+                }
+            }
+        }
+        for sfx in suffixes.iter() {
+            for rule in sfx.rules.iter() {
+                let w = match rule.apply_suffix(&stem) {
+                    Some(w) => w,
+                    None => continue
+                };
+                // a suffix and a prefix can both be attached, if both
+                // allow it.
+                if sfx.cross_product {
+                    for pfx in prefixes.iter().filter(|p| p.cross_product) {
+                        for prule in pfx.rules.iter() {
+                            if let Some(pw) = prule.apply_prefix(&w) {
+                                words.push(pw)
+                            }
+                        }
+                    }
+                }
+                words.push(w)
+            }
+        }
+    }
+    words
+}
+
+/// Split a `.dic` line into the stem and its (unparsed) flags,
+/// dropping any morphological fields.
+fn split_entry(line: &str) -> Option<(String, String)> {
+    let entry = match line.split(['\t', ' ']).next() {
+        Some(e) if !e.is_empty() => e,
+        _ => return None
+    };
+
+    // `\/` is a literal slash in the stem.
+    let mut stem = String::new();
+    let mut chars = entry.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => if let Some(c) = chars.next() { stem.push(c) },
+            '/' => return Some((stem, chars.collect())),
+            c => stem.push(c)
+        }
+    }
+    Some((stem, String::new()))
+}
+
+/// How flags are written in the dictionary.
+#[derive(Copy, Clone, PartialEq)]
+enum FlagType {
+    /// A single character (the default).
+    Char,
+    /// Two characters.
+    Long,
+    /// Decimal numbers separated by commas.
+    Num,
+}
+
+type Flag = String;
+
+struct Affix {
+    flag: Flag,
+    cross_product: bool,
+    rules: Vec<Rule>,
+}
+
+struct Rule {
+    strip: String,
+    add: String,
+    condition: Vec<Cond>,
+}
+
+enum Cond {
+    Any,
+    Char(char),
+    OneOf(Vec<char>),
+    NoneOf(Vec<char>),
+}
+
+impl Cond {
+    fn matches(&self, c: char) -> bool {
+        match *self {
+            Cond::Any => true,
+            Cond::Char(x) => x == c,
+            Cond::OneOf(ref cs) => cs.contains(&c),
+            Cond::NoneOf(ref cs) => !cs.contains(&c),
+        }
+    }
+}
+
+impl Rule {
+    fn apply_prefix(&self, word: &str) -> Option<String> {
+        let chars = word.chars().collect::<Vec<_>>();
+        if chars.len() < self.condition.len() || !word.starts_with(&*self.strip) {
+            return None
+        }
+        if !self.condition.iter().zip(chars.iter()).all(|(cond, &c)| cond.matches(c)) {
+            return None
+        }
+        Some(format!("{}{}", self.add, &word[self.strip.len()..]))
+    }
+
+    fn apply_suffix(&self, word: &str) -> Option<String> {
+        let chars = word.chars().collect::<Vec<_>>();
+        if chars.len() < self.condition.len() || !word.ends_with(&*self.strip) {
+            return None
+        }
+        let tail = &chars[chars.len() - self.condition.len()..];
+        if !self.condition.iter().zip(tail.iter()).all(|(cond, &c)| cond.matches(c)) {
+            return None
+        }
+        Some(format!("{}{}", &word[..word.len() - self.strip.len()], self.add))
+    }
+}
+
+/// The parts of an `.aff` file relevant to generating words.
+struct Affixes {
+    flag_type: FlagType,
+    aliases: Vec<String>,
+    prefixes: Vec<Affix>,
+    suffixes: Vec<Affix>,
+    need_affix: Option<Flag>,
+    only_in_compound: Option<Flag>,
+    forbidden: Option<Flag>,
+}
+
+impl Affixes {
+    fn parse(aff: &str) -> Affixes {
+        let mut ret = Affixes {
+            flag_type: FlagType::Char,
+            aliases: vec![],
+            prefixes: vec![],
+            suffixes: vec![],
+            need_affix: None,
+            only_in_compound: None,
+            forbidden: None,
+        };
+        // the affixes are declared with a header line and then a
+        // line per rule; the rules are collected by flag.
+        let mut prefixes = HashMap::new();
+        let mut suffixes = HashMap::new();
+        let mut order = vec![];
+
+        for line in aff.lines() {
+            let parts = line.split_whitespace().collect::<Vec<_>>();
+            if parts.is_empty() || parts[0].starts_with("#") { continue }
+
+            match (parts[0], parts.len()) {
+                ("FLAG", 2) => {
+                    ret.flag_type = match parts[1] {
+                        "long" => FlagType::Long,
+                        "num" => FlagType::Num,
+                        // `UTF-8` flags are single characters, once
+                        // the file is decoded.
+                        _ => FlagType::Char,
+                    }
+                }
+                ("AF", n) if n >= 2 => {
+                    // either the count of aliases, or an alias (numbered
+                    // from 1, so the count takes the place of the 0th),
+                    // either of which may be followed by a comment.
+                    ret.aliases.push(parts[1].to_string())
+                }
+                ("NEEDAFFIX", 2) | ("PSEUDOROOT", 2) => {
+                    ret.need_affix = Some(parts[1].to_string())
+                }
+                ("ONLYINCOMPOUND", 2) => ret.only_in_compound = Some(parts[1].to_string()),
+                ("FORBIDDENWORD", 2) => ret.forbidden = Some(parts[1].to_string()),
+                ("PFX", n) | ("SFX", n) if n >= 4 => {
+                    let map = if parts[0] == "PFX" { &mut prefixes } else { &mut suffixes };
+                    // the header, `SFX flag cross_product count`, comes
+                    // first; a rule may leave out its condition, so
+                    // have four fields too.
+                    if !map.contains_key(parts[1]) && parts[3].parse::<usize>().is_ok() {
+                        let flag = parts[1].to_string();
+                        order.push((parts[0] == "PFX", flag.clone()));
+                        map.insert(flag.clone(), Affix {
+                            flag,
+                            cross_product: parts[2] == "Y",
+                            rules: vec![],
+                        });
+                    } else if let Some(affix) = map.get_mut(parts[1]) {
+                        let strip = if parts[2] == "0" { "" } else { parts[2] };
+                        // continuation flags (`ing/S`) aren't
+                        // supported; the affix is used alone.
+                        let add = parts[3].split('/').next().unwrap();
+                        let add = if add == "0" { "" } else { add };
+                        affix.rules.push(Rule {
+                            strip: strip.to_string(),
+                            add: add.to_string(),
+                            condition: parse_condition(parts.get(4).cloned().unwrap_or(".")),
+                        })
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        for (is_prefix, flag) in order.into_iter() {
+            let map = if is_prefix { &mut prefixes } else { &mut suffixes };
+            if let Some(affix) = map.remove(&flag) {
+                if is_prefix { ret.prefixes.push(affix) } else { ret.suffixes.push(affix) }
+            }
+        }
+        ret
+    }
+
+    /// The set of flags for a dictionary entry, resolving aliases.
+    /// There is no empty flag, so an entry without flags has none.
+    fn entry_flags(&self, flags: &str) -> HashSet<Flag> {
+        let flags = match flags.parse::<usize>() {
+            Ok(n) if !self.aliases.is_empty() && n > 0 => {
+                self.aliases.get(n).map_or("", |s| &**s)
+            }
+            _ => flags
+        };
+        match self.flag_type {
+            FlagType::Char => flags.chars().map(|c| c.to_string()).collect(),
+            FlagType::Long => {
+                let chars = flags.chars().collect::<Vec<_>>();
+                chars.chunks(2).map(|c| c.iter().cloned().collect()).collect()
+            }
+            FlagType::Num => {
+                flags.split(',').map(|s| s.trim()).filter(|s| !s.is_empty())
+                     .map(|s| s.to_string()).collect()
+            }
+        }
+    }
+}
+
+/// Parse the condition of an affix rule, a simplified regular
+/// expression (`.` or a character class for each position).
+fn parse_condition(s: &str) -> Vec<Cond> {
+    let mut ret = vec![];
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '.' => ret.push(Cond::Any),
+            '[' => {
+                let mut class = vec![];
+                let mut negated = false;
+                for c in chars.by_ref() {
+                    match c {
+                        ']' => break,
+                        '^' if class.is_empty() && !negated => negated = true,
+                        c => class.push(c)
+                    }
+                }
+                ret.push(if negated { Cond::NoneOf(class) } else { Cond::OneOf(class) })
+            }
+            c => ret.push(Cond::Char(c))
+        }
+    }
+    if ret.len() == 1 {
+        if let Cond::Any = ret[0] {
+            // `.` is "no condition" rather than "at least one
+            // character".
+            return vec![]
+        }
+    }
+    ret
+}
+
+#[test]
+fn test_expand() {
+    let aff = "\
+SET UTF-8
+PFX A Y 1
+PFX A   0     re         .
+
+SFX D Y 4
+SFX D   0     d          e
+SFX D   y     ied        [^aeiou]y
+SFX D   0     ed         [^ey]
+SFX D   0     ed         [aeiou]y
+
+SFX S N 1
+SFX S   0     s          .
+";
+    let dic = "3\ncreate/AD\ntry/DS\nplay/D\tpo:verb\n";
+
+    let mut words = expand(dic, aff);
+    words.sort();
+    assert_eq!(words, vec!["create", "created", "play", "played", "recreate", "recreated",
+                           "tried", "try", "trys"]);
+
+    let mut words = expand("3\ncat/1\ndog\nbird\n", "FLAG num\nSFX 1 Y 1\nSFX 1 0 s .\n");
+    words.sort();
+    assert_eq!(words, vec!["bird", "cat", "cats", "dog"]);
+
+    // aliases, with comments.
+    let aff = "AF 2 # count\nAF A # 1\nAF AB # 2\nSFX A Y 1\nSFX A 0 s .\n\
+               SFX B Y 1\nSFX B 0 ed .\n";
+    let mut words = expand("2\ncat/1\nplay/2\n", aff);
+    words.sort();
+    assert_eq!(words, vec!["cat", "cats", "play", "played", "plays"]);
+
+    // rules without a condition.
+    let aff = "SFX A Y 2\nSFX A 0 s\nSFX A 0 ed\n";
+    let mut words = expand("1\nplay/A\n", aff);
+    words.sort();
+    assert_eq!(words, vec!["play", "played", "plays"]);
+}
@@ -1,18 +1,47 @@
 #![crate_name = "spellck"]
-#![feature(plugin_registrar, rustc_private)]
+#![cfg_attr(feature = "plugin", feature(plugin_registrar, rustc_private))]
 
-extern crate syntax;
-#[macro_use] extern crate rustc;
+#[cfg(feature = "plugin")] extern crate syntax;
+#[cfg(feature = "plugin")] #[macro_use] extern crate rustc;
 
 extern crate stem;
+extern crate toml;
+extern crate unicode_segmentation;
+extern crate unicode_normalization;
+extern crate syn;
+extern crate proc_macro2;
+extern crate pulldown_cmark;
+#[macro_use] extern crate serde_json;
 
+#[cfg(feature = "plugin")]
 use rustc::plugin::Registry;
 
 pub mod words;
+pub mod markdown;
+pub mod check;
+pub mod source;
+pub mod cfg;
+pub mod exports;
+#[cfg(feature = "plugin")]
 pub mod visitor;
+pub mod suggest;
+pub mod dict;
+pub mod hunspell;
+pub mod compiled;
+pub mod builtin;
+pub mod config;
+pub mod wordmap;
+pub mod project;
+pub mod report;
+pub mod cargo;
+pub mod lsp;
 
+#[cfg(feature = "plugin")]
 mod lint;
 
+pub use check::{Checker, Kind, Misspelling, Span, check_identifier, check_prose};
+
+#[cfg(feature = "plugin")]
 #[plugin_registrar]
 pub fn plugin_registrar(reg: &mut Registry) {
     reg.register_lint_pass(Box::new(lint::Misspellings::load()));
@@ -1,9 +1,5 @@
 use std::env;
-use std::io::prelude::*;
-use std::io::BufReader;
-use std::fs::File;
 use std::collections::HashSet;
-use std::borrow::ToOwned;
 
 use syntax::attr;
 use syntax::ast;
@@ -13,41 +9,48 @@ use syntax::ast::MetaItem_::{MetaNameValue};
 use rustc::lint::{Context, LintArray, LintPass};
 
 use visitor;
+use visitor::Kind;
+use dict;
+use builtin;
+use config::Config;
+use wordmap::WordMap;
 
 static DICT_ENV_VAR: &'static str = "SPELLCK_LINT_DICT";
+static NO_RUST_DICT_ENV_VAR: &'static str = "SPELLCK_LINT_NO_RUST_DICT";
 
 declare_lint! { MISSPELLINGS, Warn, "detects words that are spelled incorrectly" }
+declare_lint! { FORBIDDEN_WORDS, Warn, "detects words that are on the project's deny list" }
+declare_lint! { KNOWN_MISSPELLINGS, Warn, "detects common misspellings of words" }
 
 pub struct Misspellings {
-    words: HashSet<String>,
+    words: dict::Chain<'static>,
+    /// Whether any dictionaries were given by `SPELLCK_LINT_DICT`.
+    dicts_given: bool,
     loading_error: Option<String>
 }
 
 impl Misspellings {
     pub fn load() -> Misspellings {
         let mut ret = Misspellings {
-            words: HashSet::new(),
+            words: dict::Chain::new(),
+            dicts_given: false,
             loading_error: None
         };
+        // the Rust vocabulary goes underneath everything else.
+        if env::var_os(NO_RUST_DICT_ENV_VAR).is_none() {
+            ret.words.push(builtin::rust());
+        }
+
         let paths = match env::var(DICT_ENV_VAR) {
             Ok(p) => p,
-            Err(_) => {
-                ret.loading_error = Some(format!("environment variable `{}` not specified",
-                                                 DICT_ENV_VAR));
-                return ret
-            }
+            // the crate's configuration may still give some.
+            Err(_) => return ret
         };
 
+        ret.dicts_given = true;
         for p in env::split_paths(&paths) {
-            let words = File::open(&p)
-                .and_then(|f| {
-                    let rdr = BufReader::new(f);
-                    let lines = rdr.lines().map(|l| l.map(|s| s.trim().to_string()));
-                    lines.collect::<Result<Vec<String>, _>>()
-                });
-
-            match words {
-                Ok(w) => ret.words.extend(w.into_iter()),
+            match dict::load(&p) {
+                Ok(d) => ret.words.push_boxed(d),
                 Err(e) => {
                     ret.loading_error = Some(format!("error loading `{:?}`: {}", p, e));
                     return ret
@@ -61,7 +64,7 @@ impl Misspellings {
 
 impl LintPass for Misspellings {
     fn get_lints(&self) -> LintArray {
-        lint_array!(MISSPELLINGS)
+        lint_array!(MISSPELLINGS, FORBIDDEN_WORDS, KNOWN_MISSPELLINGS)
     }
 
     fn check_crate(&mut self, cx: &Context, krate: &ast::Crate) {
@@ -74,12 +77,58 @@ impl LintPass for Misspellings {
             }
         }
 
+        let mut config = Config::default();
+        if let Some(ref root) = sess.local_crate_source_file {
+            match Config::find(root) {
+                Ok(Some(c)) => config = c,
+                Ok(None) => {}
+                Err(e) => {
+                    sess.err(&format!("failed to start misspelling lint: {}", e));
+                    return
+                }
+            }
+
+            for path in dict::project_word_files(root) {
+                match dict::load(&path) {
+                    Ok(d) => self.words.push_boxed(d),
+                    Err(e) => sess.err(&format!("error loading `{}`: {}", path.display(), e))
+                }
+            }
+        }
+
+        for path in config.dictionaries.iter() {
+            match dict::load(path) {
+                Ok(d) => self.words.push_boxed(d),
+                Err(e) => {
+                    sess.err(&format!("failed to start misspelling lint: error loading `{}`: {}",
+                                      path.display(), e));
+                    return
+                }
+            }
+        }
+        // configured dictionaries add to the English words, as they do
+        // for the binaries; only looking for known misspellings
+        // doesn't need a dictionary.
+        if !self.dicts_given && !config.known_misspellings_only {
+            match builtin::english() {
+                Some(words) => self.words.push(words),
+                // without the bundled words, the configured ones will do.
+                None if !config.dictionaries.is_empty() => {}
+                None => {
+                    sess.err(&format!("failed to start misspelling lint: environment variable \
+                                       `{}` not specified", DICT_ENV_VAR));
+                    return
+                }
+            }
+        }
+
+        let mut extra_words = config.extra_words.iter().cloned().collect::<HashSet<_>>();
         for attribute in krate.attrs.iter() {
             if let MetaNameValue(ref name, ref lit) = attribute.node.value.node {
                 if &**name == "spellck_extra_words" {
                     attr::mark_used(attribute);
                     if let LitStr(ref raw_words, _) = lit.node {
-                        self.words.extend(raw_words.split_whitespace().map(|w| w.to_owned()));
+                        extra_words.extend(raw_words.split_whitespace().map(dict::normalize));
                     } else {
                         cx.sess().span_err(attribute.span, "malformed `spellck_extra_words` attribute")
                     }
@@ -87,14 +136,44 @@ impl LintPass for Misspellings {
             }
         }
 
-        let mut v = visitor::SpellingVisitor::new(&self.words, cx.exported_items);
+        self.words.push(extra_words);
+
+        let mut forbidden = WordMap::new();
+        for path in config.forbidden_words.iter() {
+            if let Err(e) = forbidden.load_into(path) {
+                sess.err(&format!("failed to start misspelling lint: error loading `{}`: {}",
+                                  path.display(), e));
+                return
+            }
+        }
+
+        let mut known_misspellings = builtin::misspellings();
+        for path in config.known_misspellings.iter() {
+            if let Err(e) = known_misspellings.load_into(path) {
+                sess.err(&format!("failed to start misspelling lint: error loading `{}`: {}",
+                                  path.display(), e));
+                return
+            }
+        }
+
+        let mut v = visitor::SpellingVisitor::with_config(&self.words, cx.exported_items, config);
+        v.set_forbidden(forbidden);
+        v.set_known_misspellings(known_misspellings);
         v.check_crate(krate);
 
         for (&pos, words) in v.misspellings.iter() {
-            sess.add_lint(MISSPELLINGS, pos.id, pos.span,
-                          format!("misspelled word{}: {}",
-                                  if words.len() == 1 { "" } else { "s" },
-                                  words.connect(", ")))
+            for (heading, group) in visitor::group_by_heading(words) {
+                let described = group.iter().map(|m| m.describe(&self.words)).collect::<Vec<_>>();
+                let lint = match group[0].kind {
+                    Kind::Forbidden(_) => FORBIDDEN_WORDS,
+                    Kind::KnownMisspelling(_) => KNOWN_MISSPELLINGS,
+                    _ => MISSPELLINGS
+                };
+                sess.add_lint(lint, pos.id, pos.span,
+                              format!("{}{}: {}", heading,
+                                      if group.len() == 1 { "" } else { "s" },
+                                      described.connect(", ")))
+            }
         }
     }
 }
@@ -0,0 +1,672 @@
+//! A language server, run by `spellck lsp`, so that editors can show
+//! misspellings as they're made, and fix them.
+//!
+//! It speaks the Language Server Protocol over stdin and stdout. Each
+//! document is checked when it is opened and whenever it changes, as
+//! part of the crate it belongs to (one of the targets of its Cargo
+//! package, or a crate of its own if it isn't in one), with the text
+//! in the editor of every open document rather than what's on disk,
+//! and the misspellings in it are published as diagnostics. For each
+//! one there are code actions to:
+//!
+//! - replace a word in a doc comment with one of the suggestions,
+//! - add the word to the project dictionary (the closest
+//!   `.spellck-words`, which is created next to `Cargo.toml` if there
+//!   isn't one), or
+//! - add the word to the crate's `spellck_extra_words` attribute.
+
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, Read, Write};
+use std::path::{Path, PathBuf};
+
+use serde_json::{self, Value};
+use syn;
+
+use cargo::{self, Package};
+use check::{Kind, Misspelling};
+use dict::{self, Dictionary};
+use project::Project;
+use source::{self, Crate, SourceFile, SourceMap};
+use {suggest, wordmap};
+
+/// The command that adds a word to a project dictionary, with
+/// arguments the word and the path of the dictionary.
+static ADD_TO_DICTIONARY: &str = "spellck.addToDictionary";
+
+/// The number of suggested replacements offered as code actions.
+const SUGGESTIONS: usize = 5;
+
+/// Run a server on stdin and stdout until the client asks it to exit,
+/// checking against `words` along with each project's own words.
+pub fn run(words: dict::Chain<'static>) -> io::Result<()> {
+    let stdin = io::stdin();
+    let mut server = Server::new(words, io::stdout());
+    let mut input = stdin.lock();
+    while let Some(msg) = read_message(&mut input)? {
+        if !server.handle(msg)? { break }
+    }
+    Ok(())
+}
+
+/// Read a message, framed by a `Content-Length` header; `None` at the
+/// end of the input.
+pub fn read_message<R: BufRead>(input: &mut R) -> io::Result<Option<Value>> {
+    let mut length = None;
+    loop {
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 { return Ok(None) }
+        let line = line.trim_end();
+        if line.is_empty() { break }
+
+        let mut parts = line.splitn(2, ':');
+        let name = parts.next().unwrap_or("");
+        if name.eq_ignore_ascii_case("content-length") {
+            length = parts.next().and_then(|l| l.trim().parse::<usize>().ok());
+        }
+    }
+
+    let length = match length {
+        Some(l) => l,
+        None => return Err(io::Error::new(io::ErrorKind::InvalidData,
+                                          "message without a `Content-Length`"))
+    };
+    let mut body = vec![0; length];
+    input.read_exact(&mut body)?;
+    serde_json::from_slice(&body).map(Some)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+/// Write `msg`, with its `Content-Length` header.
+pub fn write_message<W: Write>(out: &mut W, msg: &Value) -> io::Result<()> {
+    let body = msg.to_string();
+    write!(out, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    out.flush()
+}
+
+/// An open document.
+struct Document {
+    path: PathBuf,
+    /// The text, as last sent by the client.
+    text: String,
+    /// The version of `text`, if the client numbers them.
+    version: Option<i64>,
+    /// What was found in `text`, which is checked every time it
+    /// changes.
+    found: Vec<Found>,
+}
+
+/// A misspelling found in a document, with what's needed to fix it.
+struct Found {
+    diagnostic: Value,
+    misspelling: Misspelling,
+    /// The root file of the crate the document was checked as part
+    /// of.
+    crate_root: PathBuf,
+    /// Replacements for the word, if it can be replaced where it is.
+    suggestions: Vec<String>,
+}
+
+/// The state of the server.
+pub struct Server<W> {
+    out: W,
+    words: dict::Chain<'static>,
+    /// The open documents, by URI.
+    documents: HashMap<String, Document>,
+}
+
+impl<W: Write> Server<W> {
+    /// Create a server that writes its messages to `out`.
+    pub fn new(words: dict::Chain<'static>, out: W) -> Server<W> {
+        Server { out, words, documents: HashMap::new() }
+    }
+
+    /// Handle a message from the client, returning `false` if the
+    /// server should exit.
+    pub fn handle(&mut self, msg: Value) -> io::Result<bool> {
+        let method = msg["method"].as_str().unwrap_or("").to_string();
+        let params = &msg["params"];
+        let result = match &*method {
+            "initialize" => Some(json!({
+                "capabilities": {
+                    "textDocumentSync": {
+                        "openClose": true,
+                        // the full text, which is checked instead of
+                        // the file on disk.
+                        "change": 1,
+                        "save": { "includeText": false },
+                    },
+                    "codeActionProvider": true,
+                    "executeCommandProvider": { "commands": [ADD_TO_DICTIONARY] },
+                },
+                "serverInfo": { "name": "spellck" },
+            })),
+            "shutdown" => Some(Value::Null),
+            "exit" => return Ok(false),
+            "textDocument/didOpen" => {
+                let doc = &params["textDocument"];
+                if let (Some(uri), Some(text)) = (doc["uri"].as_str(), doc["text"].as_str()) {
+                    if let Some(path) = uri_to_path(uri) {
+                        self.documents.insert(uri.to_string(), Document {
+                            path,
+                            text: text.to_string(),
+                            version: doc["version"].as_i64(),
+                            found: vec![],
+                        });
+                        self.check(uri)?;
+                    }
+                }
+                None
+            }
+            "textDocument/didChange" => {
+                let uri = params["textDocument"]["uri"].as_str().unwrap_or("");
+                let changes = params["contentChanges"].as_array();
+                let text = changes.and_then(|c| c.last()).and_then(|c| c["text"].as_str());
+                if let (Some(doc), Some(text)) = (self.documents.get_mut(uri), text) {
+                    doc.text = text.to_string();
+                    doc.version = params["textDocument"]["version"].as_i64();
+                }
+                self.check(uri)?;
+                None
+            }
+            "textDocument/didSave" => {
+                if let Some(uri) = params["textDocument"]["uri"].as_str() {
+                    self.check(uri)?;
+                }
+                None
+            }
+            "textDocument/didClose" => {
+                if let Some(uri) = params["textDocument"]["uri"].as_str() {
+                    self.documents.remove(uri);
+                    self.publish(uri, vec![])?;
+                }
+                None
+            }
+            "textDocument/codeAction" => Some(self.code_actions(params)),
+            "workspace/executeCommand" => {
+                let args = params["arguments"].as_array().map_or(&[][..], |a| &a[..]);
+                let word = args.first().and_then(|w| w.as_str());
+                let path = args.get(1).and_then(|p| p.as_str());
+                match (params["command"].as_str(), word, path) {
+                    (Some(c), Some(word), Some(path)) if c == ADD_TO_DICTIONARY => {
+                        if let Err(e) = add_to_dictionary(Path::new(path), word) {
+                            self.show_error(&format!("error writing to {}: {}", path, e))?;
+                        }
+                        self.check_all()?;
+                    }
+                    _ => {}
+                }
+                Some(Value::Null)
+            }
+            _ => None
+        };
+
+        if let Some(id) = msg.get("id") {
+            let response = match result {
+                Some(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+                None => json!({
+                    "jsonrpc": "2.0",
+                    "id": id,
+                    "error": {
+                        "code": -32601,
+                        "message": format!("unsupported request `{}`", method),
+                    },
+                }),
+            };
+            write_message(&mut self.out, &response)?;
+        }
+        Ok(true)
+    }
+
+    fn notify(&mut self, method: &str, params: Value) -> io::Result<()> {
+        let msg = json!({ "jsonrpc": "2.0", "method": method, "params": params });
+        write_message(&mut self.out, &msg)
+    }
+
+    fn publish(&mut self, uri: &str, diagnostics: Vec<Value>) -> io::Result<()> {
+        self.notify("textDocument/publishDiagnostics",
+                    json!({ "uri": uri, "diagnostics": diagnostics }))
+    }
+
+    fn show_error(&mut self, message: &str) -> io::Result<()> {
+        self.notify("window/showMessage", json!({ "type": 1, "message": message }))
+    }
+
+    fn check_all(&mut self) -> io::Result<()> {
+        let uris = self.documents.keys().cloned().collect::<Vec<_>>();
+        for uri in uris.iter() {
+            self.check(uri)?;
+        }
+        Ok(())
+    }
+
+    /// Check the document `uri`, and publish what's found.
+    fn check(&mut self, uri: &str) -> io::Result<()> {
+        let path = match self.documents.get(uri) {
+            Some(doc) => doc.path.clone(),
+            None => return Ok(())
+        };
+        // the crate is loaded with the text of any of its files that
+        // are open, which may not have been saved.
+        let overlay = self.documents.values().map(|d| {
+            (fs::canonicalize(&d.path).unwrap_or(d.path.clone()), d.text.clone())
+        }).collect::<HashMap<_, _>>();
+        let found = match check_document(&self.words, &path, &overlay) {
+            Ok(found) => found,
+            Err(e) => {
+                // probably a syntax error, which the user will soon
+                // fix, so there's no point in complaining loudly.
+                self.notify("window/logMessage", json!({ "type": 2, "message": e }))?;
+                vec![]
+            }
+        };
+        let diagnostics = found.iter().map(|f| f.diagnostic.clone()).collect();
+        if let Some(doc) = self.documents.get_mut(uri) {
+            doc.found = found;
+        }
+        self.publish(uri, diagnostics)
+    }
+
+    /// The code actions for the misspellings in the range of a
+    /// `textDocument/codeAction` request.
+    fn code_actions(&self, params: &Value) -> Value {
+        let uri = params["textDocument"]["uri"].as_str().unwrap_or("");
+        let doc = match self.documents.get(uri) {
+            Some(doc) => doc,
+            None => return json!([])
+        };
+        let (start, end) = (position_key(&params["range"]["start"]),
+                            position_key(&params["range"]["end"]));
+
+        let mut actions = vec![];
+        for f in doc.found.iter() {
+            let range = &f.diagnostic["range"];
+            if position_key(&range["end"]) < start || position_key(&range["start"]) > end {
+                continue
+            }
+            let word = &f.misspelling.word;
+
+            for (i, s) in f.suggestions.iter().enumerate() {
+                let edit = json!({ "range": range, "newText": s });
+                actions.push(json!({
+                    "title": format!("Replace with `{}`", s),
+                    "kind": "quickfix",
+                    "diagnostics": [f.diagnostic],
+                    "isPreferred": i == 0 && f.suggestions.len() == 1,
+                    "edit": workspace_edit(uri, doc.version, edit),
+                }));
+            }
+
+            // adding a word to a dictionary doesn't stop it being
+            // reported as known misspelling, forbidden, or wrongly
+            // capitalised.
+            match f.misspelling.kind {
+                Kind::Unknown | Kind::MissingSeparator(_) => {}
+                _ => continue
+            }
+
+            let dict_path = project_dictionary(&f.crate_root);
+            actions.push(json!({
+                "title": format!("Add `{}` to {}", word, dict_path.display()),
+                "kind": "quickfix",
+                "diagnostics": [f.diagnostic],
+                "command": {
+                    "title": "Add to the project dictionary",
+                    "command": ADD_TO_DICTIONARY,
+                    "arguments": [word, dict_path.display().to_string()],
+                },
+            }));
+
+            let root_doc = self.documents.iter().find(|&(_, d)| same_file(&d.path, &f.crate_root));
+            let (root_uri, root_text, root_version) = match root_doc {
+                Some((uri, d)) => (uri.clone(), Some(d.text.clone()), d.version),
+                None => (path_to_uri(&f.crate_root), read_file(&f.crate_root).ok(), None)
+            };
+            if let Some(edit) = root_text.and_then(|t| extra_words_edit(&t, word)) {
+                actions.push(json!({
+                    "title": format!("Add `{}` to `spellck_extra_words`", word),
+                    "kind": "quickfix",
+                    "diagnostics": [f.diagnostic],
+                    "edit": workspace_edit(&root_uri, root_version, edit),
+                }));
+            }
+        }
+        Value::Array(actions)
+    }
+}
+
+/// A workspace edit making `edit` to the document `uri`, which is only
+/// to be applied to `version` of it, since the edit's range is only
+/// right for that text. A `version` of `None` is the file on disk.
+fn workspace_edit(uri: &str, version: Option<i64>, edit: Value) -> Value {
+    json!({
+        "documentChanges": [{
+            "textDocument": { "uri": uri, "version": version },
+            "edits": [edit],
+        }],
+    })
+}
+
+/// Check the crate containing the file at `path`, returning the
+/// misspellings in that file. The text of the files in `overlay` is
+/// used in place of what's on disk.
+fn check_document(words: &dict::Chain, path: &Path,
+                  overlay: &HashMap<PathBuf, String>) -> Result<Vec<Found>, String> {
+    let krate = match find_crate(path, overlay)? {
+        Some(krate) => krate,
+        None => Crate::load_with_overlay(path, None, overlay)?
+    };
+    let root = PathBuf::from(&krate.source_map.file(krate.root_file()).name);
+    let project = Project::load(&root)?;
+
+    let mut crate_words = dict::Chain::new();
+    crate_words.push(words);
+    crate_words.push(&project.words);
+    crate_words.push(krate.extra_words());
+    let dict = &crate_words;
+
+    let mut checker = project.checker(&crate_words);
+    let misspellings = source::check_crate(&mut checker, &krate);
+
+    let mut found = vec![];
+    for (&sp, ms) in misspellings.iter() {
+        let (file, lo) = krate.source_map.lookup_byte_offset(sp.lo);
+        if !same_file(Path::new(&file.name), path) { continue }
+
+        for m in ms.iter() {
+            let word_sp = m.span.unwrap_or(sp);
+            let lo = lo + (word_sp.lo - sp.lo);
+            let range = range(file, lo, lo + (word_sp.hi - word_sp.lo));
+            let diagnostic = json!({
+                "range": range,
+                "severity": 2,
+                "code": m.kind.lint_name(),
+                "source": "spellck",
+                "message": format!("{}: {}", m.kind.heading(), m.describe(dict)),
+            });
+            found.push(Found {
+                diagnostic,
+                misspelling: m.clone(),
+                crate_root: root.clone(),
+                suggestions: suggestions(m, dict),
+            });
+        }
+    }
+    Ok(found)
+}
+
+/// The crate of one of the targets of the package containing `path`
+/// that includes the file at `path`, with its default features, and
+/// the files in `overlay` as they are there.
+fn find_crate(path: &Path, overlay: &HashMap<PathBuf, String>) -> Result<Option<Crate>, String> {
+    let manifest = match path.parent().and_then(cargo::find_manifest) {
+        Some(m) => m,
+        None => return Ok(None)
+    };
+    let package = match Package::load(&manifest) {
+        Ok(p) => p,
+        // e.g. a workspace root, which has no package of its own.
+        Err(_) => return Ok(None)
+    };
+    let features = package.enabled_features(&[], false, true);
+
+    // the target rooted at `path` itself is the most likely, and
+    // doesn't need to be parsed to be sure.
+    let mut targets = package.targets.iter().collect::<Vec<_>>();
+    targets.sort_by_key(|t| !same_file(&t.path, path));
+    for target in targets {
+        let cfg = package.cfg(target, &features);
+        let krate = match Crate::load_with_overlay(&target.path, Some(&cfg), overlay) {
+            Ok(k) => k,
+            Err(_) => continue
+        };
+        if krate.source_map.files().iter().any(|f| same_file(Path::new(&f.name), path)) {
+            return Ok(Some(krate))
+        }
+    }
+    Ok(None)
+}
+
+fn same_file(a: &Path, b: &Path) -> bool {
+    a == b || match (fs::canonicalize(a), fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false
+    }
+}
+
+/// The replacements to offer for `m`, which are only offered for
+/// words whose exact position is known, since an identifier has to be
+/// renamed everywhere it's used.
+fn suggestions(m: &Misspelling, dict: &dyn Dictionary) -> Vec<String> {
+    if m.span.is_none() { return vec![] }
+    match m.kind {
+        Kind::Capitalization(ref c) => vec![c.clone()],
+        Kind::KnownMisspelling(ref r) | Kind::Forbidden(Some(ref r)) => {
+            wordmap::alternatives(r).iter().map(|a| a.to_string()).collect()
+        }
+        Kind::Forbidden(None) => vec![],
+        Kind::Unknown | Kind::MissingSeparator(_) => {
+            suggest::suggestions(&m.word, dict, SUGGESTIONS)
+        }
+    }
+}
+
+/// The project dictionary of the crate with root file `crate_root`:
+/// the closest `.spellck-words` file, or a new one next to the closest
+/// `Cargo.toml` (or the crate root) if there isn't one.
+fn project_dictionary(crate_root: &Path) -> PathBuf {
+    if let Some(path) = dict::project_word_files(crate_root).into_iter().next() {
+        return path
+    }
+    let dirs = dict::project_dirs(crate_root);
+    let dir = dirs.iter().find(|d| d.join("Cargo.toml").is_file())
+        .or(dirs.first())
+        .map_or(PathBuf::from("."), |d| d.clone());
+    dir.join(dict::PROJECT_WORDS_FILE)
+}
+
+/// Append `word` to the word list at `path`, creating it if needed.
+fn add_to_dictionary(path: &Path, word: &str) -> io::Result<()> {
+    // don't join the word onto an unterminated last line.
+    let needs_newline = read_file(path).map(|s| !s.is_empty() && !s.ends_with('\n'))
+                                       .unwrap_or(false);
+    let mut f = OpenOptions::new().append(true).create(true).open(path)?;
+    if needs_newline {
+        writeln!(f)?;
+    }
+    writeln!(f, "{}", word)
+}
+
+/// The edit to the text `root` of a crate root that adds `word` to
+/// its `spellck_extra_words` attribute, or adds the attribute (in the
+/// form that compiles without the plugin) after the other inner
+/// attributes if there isn't one.
+fn extra_words_edit(root: &str, word: &str) -> Option<Value> {
+    let parsed = match syn::parse_file(root) {
+        Ok(f) => f,
+        Err(_) => return None
+    };
+    let mut cm = SourceMap::new();
+    let index = cm.add(String::new(), root.to_string());
+    let file = cm.file(index);
+
+    if let Some(lit) = source::extra_words_attrs(&parsed.attrs).into_iter().next() {
+        let hi = file.offset(lit.span().end());
+        // just inside the closing quote.
+        if !root[..hi].ends_with('"') { return None }
+        let pos = position(root, hi - 1);
+        let text = if lit.value().trim().is_empty() { word.to_string() }
+                   else { format!(" {}", word) };
+        return Some(json!({ "range": { "start": pos, "end": pos }, "newText": text }))
+    }
+
+    let attr = format!("#![cfg_attr(spellck, spellck_extra_words = \"{}\")]", word);
+    let (pos, text) = match parsed.attrs.last() {
+        Some(last) => {
+            let hi = file.offset(last.bracket_token.span.end());
+            (position(root, hi), format!("\n{}", attr))
+        }
+        None => (position(root, 0), format!("{}\n", attr)),
+    };
+    Some(json!({ "range": { "start": pos, "end": pos }, "newText": text }))
+}
+
+fn read_file(path: &Path) -> io::Result<String> {
+    let mut s = String::new();
+    File::open(path).and_then(|mut f| f.read_to_string(&mut s))?;
+    Ok(s)
+}
+
+/// The LSP range of the bytes `lo..hi` of `file`.
+fn range(file: &SourceFile, lo: usize, hi: usize) -> Value {
+    json!({ "start": position(&file.src, lo), "end": position(&file.src, hi) })
+}
+
+/// The LSP position of byte `offset` of `text`: the line, and the
+/// column in UTF-16 code units.
+fn position(text: &str, offset: usize) -> Value {
+    let before = &text[..offset];
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    json!({
+        "line": before.matches('\n').count(),
+        "character": before[line_start..].encode_utf16().count(),
+    })
+}
+
+/// A position, as something that can be compared.
+fn position_key(pos: &Value) -> (u64, u64) {
+    (pos["line"].as_u64().unwrap_or(0), pos["character"].as_u64().unwrap_or(0))
+}
+
+/// The path of a `file:` URI.
+fn uri_to_path(uri: &str) -> Option<PathBuf> {
+    if !uri.starts_with("file://") { return None }
+    let encoded = &uri.as_bytes()["file://".len()..];
+    let mut bytes = vec![];
+    let mut i = 0;
+    while i < encoded.len() {
+        let hex = if encoded[i] == b'%' && i + 2 < encoded.len() {
+            ::std::str::from_utf8(&encoded[i + 1..i + 3]).ok()
+                .and_then(|h| u8::from_str_radix(h, 16).ok())
+        } else {
+            None
+        };
+        match hex {
+            Some(b) => { bytes.push(b); i += 3 }
+            None => { bytes.push(encoded[i]); i += 1 }
+        }
+    }
+    let path = String::from_utf8(bytes).ok().map(PathBuf::from);
+    // `file:///C:/...` on Windows.
+    path.map(|p| {
+        let s = p.to_string_lossy().into_owned();
+        if cfg!(windows) && s.starts_with('/') && s[1..].find(':') == Some(1) {
+            PathBuf::from(&s[1..])
+        } else {
+            p
+        }
+    })
+}
+
+/// The `file:` URI of `path`.
+fn path_to_uri(path: &Path) -> String {
+    let path = path.to_string_lossy().replace('\\', "/");
+    let mut uri = if path.starts_with('/') { "file://".to_string() }
+                  else { "file:///".to_string() };
+    for &b in path.as_bytes() {
+        if b.is_ascii_alphanumeric() || b"/-_.~:".contains(&b) {
+            uri.push(b as char)
+        } else {
+            uri.push_str(&format!("%{:02X}", b))
+        }
+    }
+    uri
+}
+
+#[test]
+fn test_lsp() {
+    let mut out = vec![];
+    write_message(&mut out, &json!({ "id": 1 })).unwrap();
+    assert_eq!(out, b"Content-Length: 8\r\n\r\n{\"id\":1}");
+    let msg = read_message(&mut &out[..]).unwrap();
+    assert_eq!(msg, Some(json!({ "id": 1 })));
+
+    assert_eq!(uri_to_path("file:///a%20b/c.rs"), Some(PathBuf::from("/a b/c.rs")));
+    assert_eq!(path_to_uri(Path::new("/a b/c.rs")), "file:///a%20b/c.rs");
+    assert_eq!(position("ab\n\u{1F600}cd", 8), json!({ "line": 1, "character": 3 }));
+
+    let edit = extra_words_edit("//! Docs.\n\nfn f() {}\n", "foo").unwrap();
+    assert_eq!(edit["range"]["start"], json!({ "line": 0, "character": 9 }));
+    assert_eq!(edit["newText"], "\n#![cfg_attr(spellck, spellck_extra_words = \"foo\")]");
+    let edit = extra_words_edit("#![spellck_extra_words = \"bar\"]\n", "foo").unwrap();
+    assert_eq!(edit["range"]["start"], json!({ "line": 0, "character": 29 }));
+    assert_eq!(edit["newText"], " foo");
+}
+
+#[test]
+fn test_server() {
+    use std::{env, process};
+    use check::default_words;
+
+    // a crate of its own, whose file on disk is out of date.
+    let dir = env::temp_dir().join(format!("spellck-lsp-{}", process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("lib.rs");
+    File::create(&path).and_then(|mut f| f.write_all(b"//! Fine.\n")).unwrap();
+    let uri = path_to_uri(&path);
+    let dict_path = dir.join(dict::PROJECT_WORDS_FILE);
+
+    let mut server = Server::new(default_words(), vec![]);
+    let messages = vec![
+        json!({ "method": "textDocument/didOpen", "params": { "textDocument": {
+            "uri": uri, "languageId": "rust", "version": 1, "text": "//! A coment.\n",
+        } } }),
+        json!({ "method": "textDocument/didChange", "params": {
+            "textDocument": { "uri": uri, "version": 2 },
+            "contentChanges": [{ "text": "//! Some more coment.\n" }],
+        } }),
+        json!({ "id": 1, "method": "textDocument/codeAction", "params": {
+            "textDocument": { "uri": uri },
+            "range": { "start": { "line": 0, "character": 15 },
+                       "end": { "line": 0, "character": 15 } },
+            "context": { "diagnostics": [] },
+        } }),
+        json!({ "id": 2, "method": "workspace/executeCommand", "params": {
+            "command": ADD_TO_DICTIONARY,
+            "arguments": ["coment", dict_path.display().to_string()],
+        } }),
+    ];
+    for msg in messages {
+        assert!(server.handle(msg).unwrap());
+    }
+
+    let mut out = &server.out[..];
+    let mut replies = vec![];
+    while let Some(msg) = read_message(&mut out).unwrap() {
+        replies.push(msg);
+    }
+    let added = read_file(&dict_path);
+    fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(replies.len(), 5);
+
+    // the text in the editor is checked, not the file.
+    let diagnostics = &replies[0]["params"]["diagnostics"];
+    assert_eq!(diagnostics.as_array().unwrap().len(), 1);
+    assert_eq!(diagnostics[0]["range"]["start"], json!({ "line": 0, "character": 6 }));
+    let diagnostics = &replies[1]["params"]["diagnostics"];
+    assert_eq!(diagnostics[0]["range"]["start"], json!({ "line": 0, "character": 14 }));
+
+    // edits are for the version of the text they were worked out from.
+    assert_eq!(replies[2]["id"], 1);
+    let actions = replies[2]["result"].as_array().unwrap();
+    let replace = actions.iter().find(|a| a["title"] == "Replace with `comment`").unwrap();
+    let change = &replace["edit"]["documentChanges"][0];
+    assert_eq!(change["textDocument"], json!({ "uri": uri, "version": 2 }));
+    assert_eq!(change["edits"][0]["range"]["end"], json!({ "line": 0, "character": 20 }));
+    assert!(actions.iter().any(|a| a["command"]["command"] == ADD_TO_DICTIONARY));
+
+    // adding the word rechecks everything.
+    assert_eq!(added.unwrap(), "coment\n");
+    assert_eq!(replies[3]["params"]["diagnostics"], json!([]));
+    assert_eq!(replies[4], json!({ "jsonrpc": "2.0", "id": 2, "result": null }));
+}
@@ -0,0 +1,235 @@
+//! Finding the prose in documentation, which is written in Markdown.
+//!
+//! Only text that will be rendered as prose is worth checking: code
+//! blocks, code spans, link destinations, autolinks, raw HTML, entities
+//! and bare URLs are all skipped. The documentation is parsed as
+//! CommonMark, with the extensions that rustdoc uses, and the position
+//! of each piece of text in the source is kept, so that each
+//! misspelling can be traced back to where it is.
+
+use std::cmp;
+
+use pulldown_cmark::{CowStr, Event, LinkType, Options, Parser, Tag, TagEnd};
+
+/// The documentation of an item, joined together from its doc
+/// attributes, with the comment decoration (`///`, `/**`, etc.)
+/// removed.
+pub struct Doc {
+    /// The Markdown text.
+    pub text: String,
+    lines: Vec<Line>,
+}
+
+/// Where a line of `Doc::text` came from.
+struct Line {
+    /// The start of the line in the text.
+    start: usize,
+    /// The index of the doc string containing the line.
+    attr: usize,
+    /// The position of the line in that doc string.
+    offset: usize,
+}
+
+/// A piece of prose, which is all in one doc string.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Prose<'a> {
+    /// The index of the doc string it is in.
+    pub attr: usize,
+    /// The byte offset of the text in that doc string.
+    pub offset: usize,
+    pub text: &'a str,
+}
+
+impl Doc {
+    /// Join the doc strings of a single item, in order.
+    pub fn new<'a, I: IntoIterator<Item = &'a str>>(docs: I) -> Doc {
+        let mut raw = vec![];
+        for (attr, s) in docs.into_iter().enumerate() {
+            for (offset, line) in strip_decoration(s) {
+                raw.push((attr, offset, line));
+            }
+        }
+
+        // like rustdoc, remove the indentation that every line has
+        // (e.g. the space after `///`), so that it isn't taken to be
+        // an indented code block.
+        let indent = raw.iter()
+            .filter(|&&(_, _, line)| !line.trim().is_empty())
+            .map(|&(_, _, line)| leading_spaces(line))
+            .min()
+            .unwrap_or(0);
+
+        let mut doc = Doc { text: String::new(), lines: vec![] };
+        for (attr, offset, line) in raw {
+            let cut = cmp::min(indent, leading_spaces(line));
+            if !doc.lines.is_empty() {
+                doc.text.push('\n');
+            }
+            doc.lines.push(Line { start: doc.text.len(), attr, offset: offset + cut });
+            doc.text.push_str(&line[cut..]);
+        }
+        doc
+    }
+
+    /// The prose in the documentation, split so that each piece is
+    /// within a single line.
+    pub fn prose(&self) -> Vec<Prose<'_>> {
+        let mut ret = vec![];
+        for (lo, hi) in prose_ranges(&self.text) {
+            let mut i = match self.lines.binary_search_by(|l| l.start.cmp(&lo)) {
+                Ok(i) => i,
+                Err(i) => i - 1
+            };
+            let mut lo = lo;
+            while lo < hi {
+                let line = &self.lines[i];
+                let end = self.lines.get(i + 1).map_or(self.text.len(), |l| l.start - 1);
+                let piece_hi = cmp::min(hi, end);
+                if lo < piece_hi {
+                    ret.push(Prose {
+                        attr: line.attr,
+                        offset: line.offset + (lo - line.start),
+                        text: &self.text[lo..piece_hi],
+                    });
+                }
+                // skip the newline.
+                lo = end + 1;
+                i += 1;
+            }
+        }
+        ret
+    }
+}
+
+/// Split a doc string into lines with the comment markers removed,
+/// along with the position of each line in the string. A `#[doc =
+/// "..."]` attribute has no markers, so is just split.
+fn strip_decoration(s: &str) -> Vec<(usize, &str)> {
+    if s.starts_with("///") || s.starts_with("//!") {
+        return vec![(3, s[3..].trim_end_matches('\r'))]
+    }
+
+    let is_block = (s.starts_with("/**") || s.starts_with("/*!")) &&
+        s.len() >= 5 && s.ends_with("*/");
+    let (start, end) = if is_block { (3, s.len() - 2) } else { (0, s.len()) };
+
+    let mut ret = vec![];
+    let mut offset = start;
+    for (n, line) in s[start..end].split('\n').enumerate() {
+        // the ` * ` at the start of each line of a block comment.
+        let mut skip = 0;
+        if is_block && n > 0 {
+            let trimmed = line.trim_start();
+            if trimmed.starts_with('*') {
+                skip = line.len() - trimmed.len() + 1;
+            }
+        }
+        ret.push((offset + skip, line[skip..].trim_end_matches('\r')));
+        offset += line.len() + 1;
+    }
+    ret
+}
+
+fn leading_spaces(line: &str) -> usize {
+    line.len() - line.trim_start_matches(' ').len()
+}
+
+/// The byte ranges of `s`, a Markdown document, that are prose.
+pub fn prose_ranges(s: &str) -> Vec<(usize, usize)> {
+    let mut options = Options::empty();
+    options.insert(Options::ENABLE_TABLES);
+    options.insert(Options::ENABLE_FOOTNOTES);
+    options.insert(Options::ENABLE_STRIKETHROUGH);
+    options.insert(Options::ENABLE_TASKLISTS);
+    // like rustdoc, which resolves `[Vec]` and `[text][crate::foo]`
+    // itself, take any reference without a definition as a link.
+    let mut broken_link = |_| Some((CowStr::Borrowed(""), CowStr::Borrowed("")));
+    let parser = Parser::new_with_broken_link_callback(s, options, Some(&mut broken_link));
+
+    let mut ret = vec![];
+    // how many code blocks and autolinks the text is in.
+    let mut code = 0;
+    let mut autolinks = 0;
+    for (event, range) in parser.into_offset_iter() {
+        match event {
+            Event::Start(Tag::CodeBlock(_)) => code += 1,
+            Event::End(TagEnd::CodeBlock) => code -= 1,
+            Event::Start(Tag::Link { link_type: LinkType::Autolink, .. }) |
+            Event::Start(Tag::Link { link_type: LinkType::Email, .. }) => autolinks += 1,
+            Event::End(TagEnd::Link) if autolinks > 0 => autolinks -= 1,
+            // text that isn't written as it is in the source is an
+            // entity or an escaped character.
+            Event::Text(ref text) if code == 0 && autolinks == 0 &&
+                                     s.get(range.clone()) == Some(&**text) => {
+                text_prose(s, range.start, range.end, &mut ret)
+            }
+            _ => {}
+        }
+    }
+    ret
+}
+
+/// Add the text `s[lo..hi]` to `ret`, except for any bare URLs.
+fn text_prose(s: &str, lo: usize, hi: usize, ret: &mut Vec<(usize, usize)>) {
+    let mut start = lo;
+    let mut i = lo;
+    while i < hi {
+        let c = s[i..].chars().next().unwrap();
+        if !s[lo..i].chars().next_back().is_some_and(|c| c.is_alphanumeric()) &&
+                is_url(&s[i..hi]) {
+            let end = s[i..hi].find(|c: char| c.is_whitespace() || c == '<')
+                .map_or(hi, |j| i + j);
+            if start < i {
+                ret.push((start, i))
+            }
+            start = end;
+            i = end;
+        } else {
+            i += c.len_utf8();
+        }
+    }
+    if start < hi {
+        ret.push((start, hi))
+    }
+}
+
+/// Whether `s` starts with a URL, as recognised by GitHub's
+/// extended autolinks.
+fn is_url(s: &str) -> bool {
+    ["http://", "https://", "ftp://", "www.", "mailto:"].iter().any(|p| s.starts_with(p))
+}
+
+#[test]
+fn test_prose() {
+    fn prose(s: &str) -> Vec<&str> {
+        prose_ranges(s).into_iter().map(|(lo, hi)| &s[lo..hi]).collect()
+    }
+
+    assert_eq!(prose("Some `code` here."), vec!["Some ", " here."]);
+    assert_eq!(prose("``a ` b`` c"), vec![" c"]);
+    assert_eq!(prose("See [the docs](http://x.org/y) or <https://z.org>."),
+               vec!["See ", "the docs", " or ", "."]);
+    assert_eq!(prose("a [b][c_d] <em>e</em> f&nbsp;g www.h.com i"),
+               vec!["a ", "b", " ", "e", " f", "g ", " i"]);
+    assert_eq!(prose("Text\n\n```rust\nlet x = 1;\n```\n\n    code\nmore"),
+               vec!["Text", "more"]);
+    assert_eq!(prose("para\n    not code\n\n[r]: http://x"), vec!["para", "not code"]);
+    assert_eq!(prose("A crate \u{2014} with an em dash, and \u{201c}curly quotes\u{201d}."),
+               vec!["A crate \u{2014} with an em dash, and \u{201c}curly quotes\u{201d}."]);
+    assert_eq!(prose("caf\u{e9} \u{2014}http://x.org/\u{e9} d\u{e9}j\u{e0}"),
+               vec!["caf\u{e9} \u{2014}", " d\u{e9}j\u{e0}"]);
+    assert_eq!(prose("1. item\n\n   ```\n   code qzx\n   ```\n2. ```\n   qzx\n   ```"),
+               vec!["item"]);
+    assert_eq!(prose("> quote\n>\n> ```\n> code qzx\n> ```"), vec!["quote"]);
+    assert_eq!(prose("<pre>\nlet qzx = 1;\n</pre>\n\ntext"), vec!["text"]);
+    assert_eq!(prose("Title\n===\n\nSubtitle\n---\ntext"), vec!["Title", "Subtitle", "text"]);
+
+    let doc = Doc::new(vec!["/// Foo `bar`", "///", "/// ```", "/// baz", "/// ```", "/// qux"]);
+    assert_eq!(doc.text, "Foo `bar`\n\n```\nbaz\n```\nqux");
+    assert_eq!(doc.prose(), vec![Prose { attr: 0, offset: 4, text: "Foo " },
+                                 Prose { attr: 5, offset: 4, text: "qux" }]);
+
+    let doc = Doc::new(vec!["/**\n * Foo\n *     code?\n */"]);
+    assert_eq!(doc.prose(), vec![Prose { attr: 0, offset: 7, text: "Foo" },
+                                 Prose { attr: 0, offset: 18, text: "code?" }]);
+}
@@ -0,0 +1,100 @@
+//! Everything needed to check a particular crate: its configuration,
+//! and the word lists that it and its project use, shared by the
+//! standalone binaries.
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use builtin;
+use check::Checker;
+use config::Config;
+use dict::{self, Dictionary};
+use wordmap::WordMap;
+
+/// The dictionary used if spellck doesn't have its own English word
+/// list built in.
+pub static DEFAULT_DICT: &str = "/usr/share/dict/words";
+
+/// The dictionaries that every crate is checked against: the Rust
+/// words unless `rust_words` is false, the English ones unless
+/// `english` is false, and those at `paths`.
+pub fn base_words(rust_words: bool, english: bool,
+                  paths: &[String]) -> Result<dict::Chain<'static>, String> {
+    let mut words = dict::Chain::new();
+    if rust_words {
+        words.push(builtin::rust());
+    }
+    if english {
+        match builtin::english() {
+            Some(english) => words.push(english),
+            None => words.push_boxed(load_dict(Path::new(DEFAULT_DICT))?)
+        }
+    }
+    for path in paths.iter() {
+        words.push_boxed(load_dict(Path::new(path))?);
+    }
+    Ok(words)
+}
+
+/// The settings of the crate with a given root file.
+pub struct Project {
+    /// The configuration, or the default if there is none.
+    pub config: Config,
+    /// The project's own words: its `.spellck-words` files, the
+    /// configured dictionaries and `extra_words`.
+    pub words: dict::Chain<'static>,
+    /// The forbidden words.
+    pub forbidden: WordMap,
+    /// The built-in known misspellings, along with any configured
+    /// ones.
+    pub known_misspellings: WordMap,
+}
+
+impl Project {
+    /// Load the configuration and word lists of the crate with root
+    /// file `crate_root`.
+    pub fn load(crate_root: &Path) -> Result<Project, String> {
+        let config = match Config::find(crate_root) {
+            Ok(c) => c.unwrap_or(Config::default()),
+            Err(e) => return Err(format!("error loading configuration: {}", e))
+        };
+
+        let mut words = dict::Chain::new();
+        for path in dict::project_word_files(crate_root).iter()
+                                                      .chain(config.dictionaries.iter()) {
+            words.push_boxed(load_dict(path)?);
+        }
+        words.push(config.extra_words.iter().cloned().collect::<HashSet<_>>());
+
+        let forbidden = load_word_map(WordMap::new(), &config.forbidden_words)?;
+        let known_misspellings = load_word_map(builtin::misspellings(),
+                                               &config.known_misspellings)?;
+        Ok(Project {
+            config,
+            words,
+            forbidden,
+            known_misspellings,
+        })
+    }
+
+    /// A checker for the crate, using `words`, which should include
+    /// `self.words`.
+    pub fn checker<'a>(&self, words: &'a dyn Dictionary) -> Checker<'a> {
+        let mut checker = Checker::new(words, self.config.clone());
+        checker.set_forbidden(self.forbidden.clone());
+        checker.set_known_misspellings(self.known_misspellings.clone());
+        checker
+    }
+}
+
+fn load_dict(path: &Path) -> Result<Box<dyn Dictionary>, String> {
+    dict::load(path).map_err(|e| format!("error reading {}: {}", path.display(), e))
+}
+
+/// Add the entries of the files at `paths` to `map`.
+fn load_word_map(mut map: WordMap, paths: &[PathBuf]) -> Result<WordMap, String> {
+    for p in paths.iter() {
+        map.load_into(p).map_err(|e| format!("error reading {}: {}", p.display(), e))?;
+    }
+    Ok(map)
+}
@@ -0,0 +1,140 @@
+//! Rendering misspellings found by `source::check_crate`, for people
+//! or for tools.
+
+use std::iter::repeat_n;
+
+use check::{Misspelling, Span};
+use dict::Dictionary;
+use source::SourceMap;
+
+/// The message describing a group of misspellings that share a
+/// heading (see `check::group_by_heading`), e.g. `misspelled words:
+/// foo, bar`.
+pub fn message(heading: &str, group: &[&Misspelling], dict: &dyn Dictionary) -> String {
+    let word_vec: Vec<String> = group.iter().map(|m| m.describe(dict)).collect();
+    format!("{}{}: {}", heading,
+            if group.len() == 1 { "" } else { "s" },
+            word_vec.join(", "))
+}
+
+/// A diagnostic for humans: the message, and then the line that `sp`
+/// starts on, with the words underlined if their exact position is
+/// known.
+pub fn human(cm: &SourceMap, sp: Span, message: &str, words: &[&Misspelling]) -> String {
+    let sp_text = cm.span_to_string(sp);
+    let mut out = format!("{}: {}\n", sp_text, message);
+
+    let lo = cm.lookup_char_pos(sp.lo);
+    let hi = cm.lookup_char_pos(sp.hi);
+    if let Some(line) = lo.file.get_line(lo.line - 1) {
+        out.push_str(&format!("{}: {}\n", sp_text, line));
+
+        if lo.line == hi.line && words.iter().all(|m| m.span.is_some()) {
+            // keep any tabs, so the carets line up.
+            let indent = line.chars().take(lo.col)
+                .map(|c| if c == '\t' { '\t' } else { ' ' });
+            let underline = repeat_n(' ', sp_text.len() + 2)
+                .chain(indent)
+                .chain(repeat_n('^', hi.col - lo.col))
+                .collect::<String>();
+            out.push_str(&underline);
+            out.push('\n');
+        }
+    }
+    out
+}
+
+/// A diagnostic in the JSON format emitted by `rustc
+/// --error-format=json`, with each confident correction as a
+/// machine-applicable suggestion.
+pub fn json(cm: &SourceMap, sp: Span, message: &str,
+            words: &[&Misspelling], dict: &dyn Dictionary) -> String {
+    let children = words.iter().filter_map(|m| m.replacement(dict)).map(|r| {
+        format!("{{\"message\":{},\"code\":null,\"level\":\"help\",\
+                 \"spans\":[{}],\"children\":[],\"rendered\":null}}",
+                json_str(&format!("did you mean `{}`?", r.replacement)),
+                span_json(cm, r.span, false, Some(&r.replacement)))
+    }).collect::<Vec<_>>();
+
+    format!("{{\"message\":{},\
+             \"code\":{{\"code\":{},\"explanation\":null}},\
+             \"level\":\"warning\",\"spans\":[{}],\"children\":[{}],\"rendered\":{}}}",
+            json_str(message),
+            json_str(words[0].kind.lint_name()),
+            span_json(cm, sp, true, None),
+            children.join(","),
+            json_str(&format!("{}: warning: {}\n", cm.span_to_string(sp), message)))
+}
+
+/// Render a span in the format of `rustc --error-format=json`.
+fn span_json(cm: &SourceMap, sp: Span, primary: bool, replacement: Option<&str>) -> String {
+    let lo = cm.lookup_char_pos(sp.lo);
+    let hi = cm.lookup_char_pos(sp.hi);
+    let byte_start = cm.lookup_byte_offset(sp.lo).1;
+    let byte_end = byte_start + (sp.hi - sp.lo);
+    let (col_lo, col_hi) = (lo.col, hi.col);
+
+    // the source text of the first line, so tools can check they're
+    // editing what they think they're editing.
+    let text = match lo.file.get_line(lo.line - 1) {
+        Some(line) => {
+            let end = if lo.line == hi.line { col_hi } else { line.chars().count() };
+            format!("{{\"text\":{},\"highlight_start\":{},\"highlight_end\":{}}}",
+                    json_str(line), col_lo + 1, end + 1)
+        }
+        None => String::new()
+    };
+
+    let (replacement, applicability) = match replacement {
+        Some(r) => (json_str(r), "\"MachineApplicable\""),
+        None => ("null".to_string(), "null")
+    };
+
+    format!("{{\"file_name\":{},\"byte_start\":{},\"byte_end\":{},\
+             \"line_start\":{},\"line_end\":{},\"column_start\":{},\"column_end\":{},\
+             \"is_primary\":{},\"text\":[{}],\"label\":null,\
+             \"suggested_replacement\":{},\"suggestion_applicability\":{},\
+             \"expansion\":null}}",
+            json_str(&lo.file.name), byte_start, byte_end,
+            lo.line, hi.line, col_lo + 1, col_hi + 1,
+            primary, text, replacement, applicability)
+}
+
+/// Quote and escape a string for inclusion in JSON.
+pub fn json_str(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c)
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[test]
+fn test_report() {
+    use check::Kind;
+    use std::collections::HashSet;
+
+    let mut cm = SourceMap::new();
+    cm.add("a.rs".to_string(), "/// Teh \"end\"\nfn a() {}\n".to_string());
+    let m = Misspelling { word: "Teh".to_string(), kind: Kind::Unknown,
+                          span: Some(Span { lo: 4, hi: 7 }) };
+    let dict = HashSet::<String>::new();
+
+    let msg = message("misspelled word", &[&m], &dict);
+    assert_eq!(msg, "misspelled word: Teh");
+    assert_eq!(human(&cm, m.span.unwrap(), &msg, &[&m]),
+               "a.rs:1:5: 1:8: misspelled word: Teh\n\
+                a.rs:1:5: 1:8: /// Teh \"end\"\n\
+                \x20                  ^^^\n");
+    assert_eq!(json_str("\"a\"\n"), "\"\\\"a\\\"\\n\"");
+}
@@ -0,0 +1,673 @@
+//! Reading crates straight from their source with `syn`, so that
+//! spellck runs on any stable toolchain, without a compiler session.
+//!
+//! The whole crate is parsed, following `mod foo;` declarations to
+//! the files they refer to, and its public API (see `exports`) is
+//! checked the same way the compiler plugin checks it. Macros aren't
+//! expanded, and `#[cfg]` attributes are only evaluated if a `Cfg` is
+//! given (e.g. by `cargo spellck`, from the enabled features);
+//! otherwise items that only exist on other platforms are checked too.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::prelude::*;
+use std::path::{Path, PathBuf};
+
+use proc_macro2::{self, LineColumn};
+use syn;
+
+use cfg::Cfg;
+use check::{Checker, Misspelling, Span};
+use config::ItemKind;
+use dict;
+use exports::Exports;
+
+/// A source file, as part of a `SourceMap`.
+pub struct SourceFile {
+    /// The path of the file, as given.
+    pub name: String,
+    /// The contents of the file.
+    pub src: String,
+    /// The position of the start of the file in the `SourceMap`.
+    pub start: usize,
+    /// The byte offsets of the start of each line.
+    lines: Vec<usize>,
+}
+
+impl SourceFile {
+    fn new(name: String, src: String, start: usize) -> SourceFile {
+        let mut lines = vec![0];
+        lines.extend(src.match_indices('\n').map(|(i, _)| i + 1));
+        SourceFile { name, src, start, lines }
+    }
+
+    /// The text of the `line`th line (counting from zero), without
+    /// the newline.
+    pub fn get_line(&self, line: usize) -> Option<&str> {
+        self.lines.get(line).map(|&lo| {
+            let rest = &self.src[lo..];
+            let line = rest.find('\n').map_or(rest, |hi| &rest[..hi]);
+            line.trim_end_matches('\r')
+        })
+    }
+
+    /// The byte offset in the file of a line (counting from one) and
+    /// column (in characters, from zero), as given by `syn`.
+    pub fn offset(&self, lc: LineColumn) -> usize {
+        let lo = match self.lines.get(lc.line.saturating_sub(1)) {
+            Some(&lo) => lo,
+            None => return self.src.len()
+        };
+        self.src[lo..].char_indices().nth(lc.column).map_or(self.src.len(), |(i, _)| lo + i)
+    }
+}
+
+/// A location in a `SourceMap`: a line (counting from one) and column
+/// (in characters, counting from zero) of a file.
+pub struct Loc<'a> {
+    pub file: &'a SourceFile,
+    pub line: usize,
+    pub col: usize,
+}
+
+/// A set of source files, laid out one after another so that a
+/// single position identifies a file as well as a place in it.
+#[derive(Default)]
+pub struct SourceMap {
+    files: Vec<SourceFile>,
+}
+
+impl SourceMap {
+    /// Create an empty source map.
+    pub fn new() -> SourceMap {
+        SourceMap::default()
+    }
+
+    /// Add a file, returning its index.
+    pub fn add(&mut self, name: String, src: String) -> usize {
+        // leave a gap after each file, so that the end of one
+        // doesn't coincide with the start of the next.
+        let start = self.files.last().map_or(0, |f| f.start + f.src.len() + 1);
+        self.files.push(SourceFile::new(name, src, start));
+        self.files.len() - 1
+    }
+
+    /// The file with the given index.
+    pub fn file(&self, index: usize) -> &SourceFile {
+        &self.files[index]
+    }
+
+    /// All the files, in the order they were added.
+    pub fn files(&self) -> &[SourceFile] {
+        &self.files
+    }
+
+    /// The file containing `pos`, and the byte offset of `pos` in it.
+    pub fn lookup_byte_offset(&self, pos: usize) -> (&SourceFile, usize) {
+        let i = match self.files.binary_search_by(|f| f.start.cmp(&pos)) {
+            Ok(i) => i,
+            Err(i) => i - 1
+        };
+        let file = &self.files[i];
+        (file, pos - file.start)
+    }
+
+    /// The line and column of `pos`.
+    pub fn lookup_char_pos(&self, pos: usize) -> Loc<'_> {
+        let (file, offset) = self.lookup_byte_offset(pos);
+        let line = match file.lines.binary_search(&offset) {
+            Ok(i) => i,
+            Err(i) => i - 1
+        };
+        let col = file.src[file.lines[line]..offset].chars().count();
+        Loc { file, line: line + 1, col }
+    }
+
+    /// Describe a span like the compiler does, e.g.
+    /// `src/lib.rs:6:1: 6:19`.
+    pub fn span_to_string(&self, sp: Span) -> String {
+        let lo = self.lookup_char_pos(sp.lo);
+        let hi = self.lookup_char_pos(sp.hi);
+        format!("{}:{}:{}: {}:{}", lo.file.name, lo.line, lo.col + 1, hi.line, hi.col + 1)
+    }
+}
+
+/// A module whose items are in a file of their own.
+struct ModFile {
+    /// The index of the file in the `SourceMap`.
+    file: usize,
+    /// The inner attributes of the file, like `//!` comments.
+    attrs: Vec<syn::Attribute>,
+}
+
+/// A crate, parsed from its source.
+pub struct Crate {
+    /// The files of the crate.
+    pub source_map: SourceMap,
+    /// The crate, with the items of each `mod foo;` filled in from
+    /// the file they're in.
+    pub file: syn::File,
+    /// The modules in files of their own, by the position of their
+    /// name in their declaration.
+    mods: HashMap<usize, ModFile>,
+}
+
+impl Crate {
+    /// Parse the crate with root file `root`, along with the files of
+    /// all its modules.
+    pub fn load(root: &Path) -> Result<Crate, String> {
+        let src = read_file(root)?;
+        Crate::parse(root, src, None, None)
+    }
+
+    /// Parse the crate with root file `root`, like `load`, but leave
+    /// out the items (and module files) that are disabled under `cfg`.
+    pub fn load_with_cfg(root: &Path, cfg: &Cfg) -> Result<Crate, String> {
+        let src = read_file(root)?;
+        Crate::parse(root, src, Some(cfg), None)
+    }
+
+    /// Parse the crate with root file `root`, like `load_with_cfg` (or
+    /// `load`, without a `cfg`), but with the text of the files in
+    /// `overlay` (e.g. unsaved changes in an editor) in place of what's
+    /// on disk.
+    pub fn load_with_overlay(root: &Path, cfg: Option<&Cfg>,
+                             overlay: &HashMap<PathBuf, String>) -> Result<Crate, String> {
+        let src = match overlay_text(overlay, root) {
+            Some(src) => src.to_string(),
+            None => read_file(root)?
+        };
+        Crate::parse(root, src, cfg, Some(overlay))
+    }
+
+    /// Parse the crate with root file `root`, which contains `src`
+    /// (e.g. unsaved changes to it), along with the files of all its
+    /// modules, which are read from disk.
+    pub fn from_source(root: &Path, src: String) -> Result<Crate, String> {
+        Crate::parse(root, src, None, None)
+    }
+
+    fn parse(root: &Path, src: String, cfg: Option<&Cfg>,
+             overlay: Option<&HashMap<PathBuf, String>>) -> Result<Crate, String> {
+        let mut loader = Loader {
+            source_map: SourceMap::new(),
+            mods: HashMap::new(),
+            cfg,
+            overlay,
+        };
+        let (index, mut file) = parse_source(&mut loader.source_map, root, src)?;
+        if !loader.enabled(&file.attrs) {
+            file.items.clear();
+        }
+        let dir = root.parent().unwrap_or(Path::new("."));
+        loader.load_mods(&mut file.items, index, dir)?;
+        Ok(Crate { source_map: loader.source_map, file, mods: loader.mods })
+    }
+
+    /// The index of the root file in the `SourceMap`.
+    pub fn root_file(&self) -> usize {
+        0
+    }
+
+    /// The position in the `SourceMap` of `sp`, which is in the file
+    /// with index `file`.
+    pub fn span(&self, file: usize, sp: proc_macro2::Span) -> Span {
+        let f = self.source_map.file(file);
+        Span { lo: f.start + f.offset(sp.start()), hi: f.start + f.offset(sp.end()) }
+    }
+
+    /// The file that the items of `m`, which is declared in `file`,
+    /// are in, along with the inner attributes of that file; `None` if
+    /// they're in the declaration, `mod foo { ... }`.
+    pub fn mod_file(&self, file: usize, m: &syn::ItemMod) -> Option<(usize, &[syn::Attribute])> {
+        self.mods.get(&self.span(file, m.ident.span()).lo).map(|m| (m.file, &*m.attrs))
+    }
+
+    /// The words listed by `#![spellck_extra_words = "..."]` attributes
+    /// of the crate (see `extra_words_attrs`), which are correct
+    /// throughout it.
+    pub fn extra_words(&self) -> HashSet<String> {
+        extra_words_attrs(&self.file.attrs).iter()
+            .flat_map(|lit| {
+                lit.value().split_whitespace().map(dict::normalize).collect::<Vec<_>>()
+            })
+            .collect()
+    }
+}
+
+fn read_file(path: &Path) -> Result<String, String> {
+    let mut src = String::new();
+    File::open(path).and_then(|mut f| f.read_to_string(&mut src))
+        .map_err(|e| format!("error reading {}: {}", path.display(), e))?;
+    Ok(src)
+}
+
+/// The text in `overlay` of the file at `path`, which may be listed
+/// under its canonical path instead.
+fn overlay_text<'a>(overlay: &'a HashMap<PathBuf, String>, path: &Path) -> Option<&'a str> {
+    overlay.get(path)
+        .or_else(|| fs::canonicalize(path).ok().and_then(|p| overlay.get(&p)))
+        .map(|s| &**s)
+}
+
+fn parse_source(source_map: &mut SourceMap, path: &Path,
+                src: String) -> Result<(usize, syn::File), String> {
+    let parsed = syn::parse_file(&src);
+    let index = source_map.add(path.display().to_string(), src);
+    match parsed {
+        Ok(file) => Ok((index, file)),
+        Err(e) => {
+            let lc = e.span().start();
+            Err(format!("{}:{}:{}: {}", path.display(), lc.line, lc.column + 1, e))
+        }
+    }
+}
+
+/// The value of a `#[path = "..."]` attribute, if there is one.
+fn path_attr(attrs: &[syn::Attribute]) -> Option<String> {
+    attrs.iter().filter(|a| a.path.is_ident("path")).filter_map(|a| {
+        match a.parse_meta() {
+            Ok(syn::Meta::NameValue(syn::MetaNameValue { lit: syn::Lit::Str(ref s), .. })) => {
+                Some(s.value())
+            }
+            _ => None
+        }
+    }).next()
+}
+
+/// The state of parsing a crate's module files.
+struct Loader<'a> {
+    source_map: SourceMap,
+    mods: HashMap<usize, ModFile>,
+    /// The configuration to evaluate `#[cfg]`s under, if any.
+    cfg: Option<&'a Cfg>,
+    /// Text to use instead of the contents of some files.
+    overlay: Option<&'a HashMap<PathBuf, String>>,
+}
+
+impl<'a> Loader<'a> {
+    fn enabled(&self, attrs: &[syn::Attribute]) -> bool {
+        self.cfg.is_none_or(|cfg| cfg.enabled(attrs))
+    }
+
+    fn parse_file(&mut self, path: &Path) -> Result<(usize, syn::File), String> {
+        let src = match self.overlay.and_then(|o| overlay_text(o, path)) {
+            Some(src) => src.to_string(),
+            None => read_file(path)?
+        };
+        parse_source(&mut self.source_map, path, src)
+    }
+
+    /// Fill in the items of the modules declared in `items`, which are
+    /// in file `file`, and whose submodules are in directory `dir`,
+    /// following the same rules as the compiler: `mod foo;` is in
+    /// `dir/foo.rs` or `dir/foo/mod.rs`, unless it has a `#[path]`
+    /// attribute. Disabled items are removed first, so the files of
+    /// disabled modules aren't read at all.
+    fn load_mods(&mut self, items: &mut Vec<syn::Item>, file: usize,
+                 dir: &Path) -> Result<(), String> {
+        items.retain(|i| self.enabled(item_attrs(i)));
+        for item in items.iter_mut() {
+            let m = match *item {
+                syn::Item::Mod(ref mut m) => m,
+                _ => continue
+            };
+            let name = unraw(&m.ident);
+            let path = path_attr(&m.attrs);
+
+            if let Some((_, ref mut items)) = m.content {
+                let dir = dir.join(path.unwrap_or(name));
+                self.load_mods(items, file, &dir)?;
+                continue
+            }
+
+            let (path, sub_dir) = match path {
+                // relative to the file the declaration is in, and used
+                // like a `mod.rs`.
+                Some(p) => {
+                    let base = Path::new(&self.source_map.file(file).name).parent()
+                        .unwrap_or(Path::new(".")).to_path_buf();
+                    let path = base.join(p);
+                    let sub_dir = path.parent().unwrap_or(Path::new(".")).to_path_buf();
+                    (path, sub_dir)
+                }
+                None => {
+                    let flat = dir.join(format!("{}.rs", name));
+                    if flat.is_file() {
+                        (flat, dir.join(&name))
+                    } else {
+                        (dir.join(&name).join("mod.rs"), dir.join(&name))
+                    }
+                }
+            };
+
+            let (index, mod_file) = self.parse_file(&path)?;
+            let syn::File { attrs, mut items, .. } = mod_file;
+            // `#![cfg(...)]` at the top of the file.
+            if !self.enabled(&attrs) {
+                items.clear();
+            }
+            self.load_mods(&mut items, index, &sub_dir)?;
+
+            let name_pos = {
+                let f = self.source_map.file(file);
+                f.start + f.offset(m.ident.span().start())
+            };
+            self.mods.insert(name_pos, ModFile { file: index, attrs });
+            m.content = Some((Default::default(), items));
+        }
+        Ok(())
+    }
+}
+
+/// The attributes of an item.
+fn item_attrs(item: &syn::Item) -> &[syn::Attribute] {
+    match *item {
+        syn::Item::Const(ref i) => &i.attrs,
+        syn::Item::Enum(ref i) => &i.attrs,
+        syn::Item::ExternCrate(ref i) => &i.attrs,
+        syn::Item::Fn(ref i) => &i.attrs,
+        syn::Item::ForeignMod(ref i) => &i.attrs,
+        syn::Item::Impl(ref i) => &i.attrs,
+        syn::Item::Macro(ref i) => &i.attrs,
+        syn::Item::Macro2(ref i) => &i.attrs,
+        syn::Item::Mod(ref i) => &i.attrs,
+        syn::Item::Static(ref i) => &i.attrs,
+        syn::Item::Struct(ref i) => &i.attrs,
+        syn::Item::Trait(ref i) => &i.attrs,
+        syn::Item::TraitAlias(ref i) => &i.attrs,
+        syn::Item::Type(ref i) => &i.attrs,
+        syn::Item::Union(ref i) => &i.attrs,
+        syn::Item::Use(ref i) => &i.attrs,
+        _ => &[]
+    }
+}
+
+/// The values of the `spellck_extra_words` attributes in `attrs`,
+/// either on their own, as the lint reads them, or as
+/// `#![cfg_attr(spellck, spellck_extra_words = "...")]`, which compiles
+/// without the plugin.
+pub fn extra_words_attrs(attrs: &[syn::Attribute]) -> Vec<syn::LitStr> {
+    fn extra_words(meta: &syn::Meta) -> Option<syn::LitStr> {
+        match *meta {
+            syn::Meta::NameValue(syn::MetaNameValue { ref path, lit: syn::Lit::Str(ref s), .. })
+                    if path.is_ident("spellck_extra_words") => Some(s.clone()),
+            _ => None
+        }
+    }
+
+    let mut lits = vec![];
+    for attr in attrs.iter() {
+        match attr.parse_meta() {
+            Ok(syn::Meta::List(ref list)) if list.path.is_ident("cfg_attr") => {
+                // the first argument is the predicate.
+                for nested in list.nested.iter().skip(1) {
+                    if let syn::NestedMeta::Meta(ref m) = *nested {
+                        lits.extend(extra_words(m));
+                    }
+                }
+            }
+            Ok(ref meta) => lits.extend(extra_words(meta)),
+            Err(_) => {}
+        }
+    }
+    lits
+}
+
+/// The name of an identifier, without any `r#`.
+pub fn unraw(ident: &syn::Ident) -> String {
+    let s = ident.to_string();
+    match s.strip_prefix("r#") {
+        Some(name) => name.to_string(),
+        None => s
+    }
+}
+
+/// Whether `vis` is `pub`, as opposed to private or restricted like
+/// `pub(crate)`.
+pub fn is_public(vis: &syn::Visibility) -> bool {
+    matches!(*vis, syn::Visibility::Public(_))
+}
+
+/// Spell-check the public API of `krate` (as worked out by `exports`),
+/// giving the misspellings at each position in its `SourceMap`.
+pub fn check_crate(checker: &mut Checker, krate: &Crate) -> BTreeMap<Span, Vec<Misspelling>> {
+    let mut walker = Walker {
+        checker,
+        krate,
+        exports: Exports::new(krate),
+        file: krate.root_file(),
+        misspellings: BTreeMap::new(),
+    };
+    walker.check_doc_attrs(&krate.file.attrs);
+    walker.walk_items(&krate.file.items);
+    walker.misspellings
+}
+
+/// Walks through a crate, checking anything that could be visible to
+/// the outside world, e.g. documentation, pub fns, pub mods etc.
+struct Walker<'a, 'b: 'a> {
+    checker: &'a mut Checker<'b>,
+    krate: &'a Crate,
+    /// The items that are part of the public API.
+    exports: Exports,
+    /// The file being walked.
+    file: usize,
+    misspellings: BTreeMap<Span, Vec<Misspelling>>,
+}
+
+impl<'a, 'b> Walker<'a, 'b> {
+    /// The position of `sp`, which is in the file being walked.
+    fn span(&self, sp: proc_macro2::Span) -> Span {
+        self.krate.span(self.file, sp)
+    }
+
+    /// Insert `m` into the misspelling map, at its exact position if
+    /// it is known, or at `pos` otherwise.
+    fn insert(&mut self, m: Misspelling, pos: Span) {
+        self.misspellings.entry(m.span.unwrap_or(pos)).or_insert(vec![]).push(m);
+    }
+
+    fn check_ident(&mut self, ident: &syn::Ident, kind: Option<ItemKind>) {
+        let pos = self.span(ident.span());
+        for m in self.checker.check_ident(&unraw(ident), kind) {
+            self.insert(m, pos)
+        }
+    }
+
+    /// Check the `#[doc = "..."]` attributes, including the comment
+    /// forms, in `attrs`.
+    fn check_doc_attrs(&mut self, attrs: &[syn::Attribute]) {
+        let mut docs = vec![];
+        for attr in attrs.iter().filter(|a| a.path.is_ident("doc")) {
+            let value = match attr.parse_meta() {
+                Ok(syn::Meta::NameValue(syn::MetaNameValue { lit: syn::Lit::Str(s), .. })) => {
+                    s.value()
+                }
+                _ => continue
+            };
+            let sp = Span {
+                lo: self.span(attr.pound_token.span).lo,
+                hi: self.span(attr.bracket_token.span).hi,
+            };
+            // a doc comment is checked as the source text, which the
+            // positions of words can be worked out from (the value of
+            // `#[doc = "..."]` may have escapes).
+            let (file, lo) = self.krate.source_map.lookup_byte_offset(sp.lo);
+            let text = &file.src[lo..lo + (sp.hi - sp.lo)];
+            let comment = ["///", "//!", "/**", "/*!"].iter().any(|c| text.starts_with(c));
+            if comment {
+                docs.push((text.to_string(), Some(sp.lo), sp));
+            } else {
+                docs.push((value, None, sp));
+            }
+        }
+
+        let texts = docs.iter().map(|&(ref s, lo, _)| (&**s, lo)).collect::<Vec<_>>();
+        for (i, m) in self.checker.check_docs(&texts) {
+            self.insert(m, docs[i].2)
+        }
+    }
+
+    /// Whether the item named by `ident` is exported.
+    fn is_exported(&self, ident: &syn::Ident) -> bool {
+        self.exports.contains(self.span(ident.span()).lo)
+    }
+
+    /// Check a named item, if it is exported, returning whether it
+    /// is.
+    fn check_item(&mut self, attrs: &[syn::Attribute], ident: &syn::Ident,
+                  kind: ItemKind) -> bool {
+        let exported = self.is_exported(ident);
+        if exported {
+            self.check_ident(ident, Some(kind));
+            self.check_doc_attrs(attrs);
+        }
+        exported
+    }
+
+    fn check_fields(&mut self, fields: &syn::Fields) {
+        if let syn::Fields::Named(ref fields) = *fields {
+            for field in fields.named.iter().filter(|f| is_public(&f.vis)) {
+                if let Some(ref ident) = field.ident {
+                    self.check_ident(ident, Some(ItemKind::Field));
+                }
+                self.check_doc_attrs(&field.attrs);
+            }
+        }
+    }
+
+    /// Walk the items of a module, whether or not it is exported,
+    /// since it may have items that are re-exported elsewhere.
+    fn walk_items(&mut self, items: &[syn::Item]) {
+        for item in items.iter() {
+            self.walk_item(item)
+        }
+    }
+
+    fn walk_item(&mut self, item: &syn::Item) {
+        match *item {
+            syn::Item::Const(ref i) => {
+                self.check_item(&i.attrs, &i.ident, ItemKind::Const);
+            }
+            syn::Item::Static(ref i) => {
+                self.check_item(&i.attrs, &i.ident, ItemKind::Static);
+            }
+            syn::Item::Fn(ref i) => {
+                self.check_item(&i.attrs, &i.sig.ident, ItemKind::Fn);
+            }
+            syn::Item::Type(ref i) => {
+                self.check_item(&i.attrs, &i.ident, ItemKind::Type);
+            }
+            syn::Item::Struct(ref i)
+                if self.check_item(&i.attrs, &i.ident, ItemKind::Struct) => {
+                    self.check_fields(&i.fields)
+                }
+            syn::Item::Union(ref i)
+                if self.check_item(&i.attrs, &i.ident, ItemKind::Struct) => {
+                    self.check_fields(&syn::Fields::Named(i.fields.clone()))
+                }
+            syn::Item::Enum(ref i) => {
+                let exported = self.check_item(&i.attrs, &i.ident, ItemKind::Enum);
+                for var in i.variants.iter() {
+                    // variants can be re-exported on their own, with a
+                    // glob.
+                    if exported || self.is_exported(&var.ident) {
+                        self.check_ident(&var.ident, Some(ItemKind::Variant));
+                        self.check_doc_attrs(&var.attrs);
+                    }
+                }
+            }
+            syn::Item::Trait(ref i)
+                if self.check_item(&i.attrs, &i.ident, ItemKind::Trait) => {
+                    for item in i.items.iter() {
+                        let (attrs, ident) = match *item {
+                            syn::TraitItem::Const(ref c) => (&c.attrs, &c.ident),
+                            syn::TraitItem::Method(ref m) => (&m.attrs, &m.sig.ident),
+                            syn::TraitItem::Type(ref t) => (&t.attrs, &t.ident),
+                            _ => continue
+                        };
+                        self.check_doc_attrs(attrs);
+                        self.check_ident(ident, Some(ItemKind::Method));
+                    }
+                }
+            syn::Item::TraitAlias(ref i) => {
+                self.check_item(&i.attrs, &i.ident, ItemKind::Trait);
+            }
+            syn::Item::Mod(ref i) => {
+                let exported = self.check_item(&i.attrs, &i.ident, ItemKind::Mod);
+                let outer = self.file;
+                if let Some((file, attrs)) = self.krate.mod_file(self.file, i) {
+                    self.file = file;
+                    if exported {
+                        self.check_doc_attrs(attrs);
+                    }
+                }
+                if let Some((_, ref items)) = i.content {
+                    self.walk_items(items);
+                }
+                self.file = outer;
+            }
+            syn::Item::ForeignMod(ref i) => {
+                for item in i.items.iter() {
+                    // don't check the ident; there's nothing the user
+                    // can do to control the name.
+                    let (attrs, ident) = match *item {
+                        syn::ForeignItem::Fn(ref f) => (&f.attrs, &f.sig.ident),
+                        syn::ForeignItem::Static(ref s) => (&s.attrs, &s.ident),
+                        syn::ForeignItem::Type(ref t) => (&t.attrs, &t.ident),
+                        _ => continue
+                    };
+                    if self.is_exported(ident) {
+                        self.check_doc_attrs(attrs);
+                    }
+                }
+            }
+            // impl Type { ... }
+            syn::Item::Impl(ref i) => {
+                if !self.exports.contains_impl(self.span(i.impl_token.span).lo) { return }
+                let is_trait = i.trait_.is_some();
+                for item in i.items.iter() {
+                    let (attrs, vis, ident) = match *item {
+                        syn::ImplItem::Const(ref c) => (&c.attrs, &c.vis, &c.ident),
+                        syn::ImplItem::Method(ref m) => (&m.attrs, &m.vis, &m.sig.ident),
+                        syn::ImplItem::Type(ref t) => (&t.attrs, &t.vis, &t.ident),
+                        _ => continue
+                    };
+                    if is_trait {
+                        // the name and visibility come from the trait.
+                        self.check_doc_attrs(attrs);
+                    } else if is_public(vis) {
+                        self.check_doc_attrs(attrs);
+                        self.check_ident(ident, Some(ItemKind::Method));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[test]
+fn test_source_map() {
+    let mut sm = SourceMap::new();
+    sm.add("a.rs".to_string(), "fn a() {}\n".to_string());
+    let b = sm.add("b.rs".to_string(), "// b\r\n/// caf\u{e9} b\n".to_string());
+    let pos = sm.file(b).start + sm.file(b).offset(LineColumn { line: 2, column: 8 });
+
+    let loc = sm.lookup_char_pos(pos);
+    assert_eq!((&*loc.file.name, loc.line, loc.col), ("b.rs", 2, 8));
+    assert_eq!(loc.file.get_line(0), Some("// b"));
+    assert_eq!(sm.lookup_byte_offset(pos).1, 15);
+    assert_eq!(sm.span_to_string(Span { lo: pos, hi: pos + 1 }), "b.rs:2:9: 2:10");
+}
+
+#[test]
+fn test_extra_words() {
+    let file = syn::parse_file("//! Docs.\n\
+                                #![spellck_extra_words = \"foo bar\"]\n\
+                                #![cfg_attr(spellck, spellck_extra_words = \"baz\")]\n\
+                                #![cfg_attr(test, allow(dead_code))]\n").unwrap();
+    let lits = extra_words_attrs(&file.attrs);
+    assert_eq!(lits.iter().map(|l| l.value()).collect::<Vec<_>>(), ["foo bar", "baz"]);
+}
@@ -0,0 +1,190 @@
+//! Suggesting corrections for misspelled words.
+
+use std::cmp;
+use std::cmp::Reverse;
+
+use dict::Dictionary;
+
+/// The number of suggestions offered for each misspelled word by
+/// default.
+pub static DEFAULT_SUGGESTIONS: usize = 3;
+
+/// The Damerau-Levenshtein distance between `a` and `b`
+/// (specifically, the "optimal string alignment" variant), that is,
+/// the number of single character insertions, deletions,
+/// substitutions and transpositions of adjacent characters required
+/// to turn one into the other. Comparisons ignore case.
+pub fn distance(a: &str, b: &str) -> usize {
+    let a = a.chars().flat_map(|c| c.to_lowercase()).collect::<Vec<_>>();
+    let b = b.chars().flat_map(|c| c.to_lowercase()).collect::<Vec<_>>();
+    osa_distance(&a, &b)
+}
+
+fn osa_distance(a: &[char], b: &[char]) -> usize {
+    // three rows of the full matrix: the transposition case needs to
+    // look back two rows.
+    let mut prev2 = vec![0; b.len() + 1];
+    let mut prev = (0..b.len() + 1).collect::<Vec<usize>>();
+    let mut cur = vec![0; b.len() + 1];
+
+    for i in 1..a.len() + 1 {
+        cur[0] = i;
+        for j in 1..b.len() + 1 {
+            let cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+            let mut d = cmp::min(cmp::min(prev[j] + 1, cur[j - 1] + 1),
+                                 prev[j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                d = cmp::min(d, prev2[j - 2] + 1)
+            }
+            cur[j] = d;
+        }
+        ::std::mem::swap(&mut prev2, &mut prev);
+        ::std::mem::swap(&mut prev, &mut cur);
+    }
+    prev[b.len()]
+}
+
+/// The largest edit distance at which a word is considered a
+/// plausible correction for `word`: short words have so many
+/// neighbours that only a single edit is useful.
+fn max_distance(len: usize) -> usize {
+    if len <= 4 { 1 } else { 2 }
+}
+
+/// Find the entries of `dict` closest to `word` by
+/// Damerau-Levenshtein distance, returning at most `max` of them,
+/// best first. Ties are broken in favour of candidates that share
+/// the first letter of `word`, then those that share the most
+/// characters with it at the start and end (so the edit is in the
+/// middle of the word, and is more often a dropped or doubled
+/// letter than a different one), then those that start with the
+/// same case as it, and then alphabetically.
+pub fn suggestions(word: &str, dict: &dyn Dictionary, max: usize) -> Vec<String> {
+    ranked(word, dict).into_iter().take(max).map(|(_, w)| w).collect()
+}
+
+/// The single correction for `word` that is confident enough to be
+/// applied automatically, if any: it has to be the only entry of
+/// `dict` a single edit away. The correction is given the
+/// capitalisation of `word` when the dictionary entry has none of
+/// its own, so `Teh` becomes `The` rather than `the`.
+pub fn correction(word: &str, dict: &dyn Dictionary) -> Option<String> {
+    let ranked = ranked(word, dict);
+    match ranked.first() {
+        Some(&(1, ref w)) if ranked.get(1).is_none_or(|&(d, _)| d > 1) => {
+            Some(match_case(word, w))
+        }
+        _ => None
+    }
+}
+
+/// Copy the capitalisation of `model` on to `word`, if `word` is
+/// entirely lowercase.
+pub fn match_case(model: &str, word: &str) -> String {
+    if word.chars().any(|c| c.is_uppercase()) {
+        return word.to_string()
+    }
+    let mut model_chars = model.chars().filter(|c| c.is_alphabetic());
+    let first_upper = model_chars.next().is_some_and(|c| c.is_uppercase());
+    let rest_upper = model_chars.all(|c| c.is_uppercase());
+
+    if first_upper && rest_upper && model.chars().count() > 1 {
+        word.to_uppercase()
+    } else if first_upper {
+        let mut chars = word.chars();
+        chars.next().map_or(String::new(), |c| {
+            let mut s = c.to_uppercase().collect::<String>();
+            s.push_str(chars.as_str());
+            s
+        })
+    } else {
+        word.to_string()
+    }
+}
+
+/// All the entries of `dict` that are plausible corrections for
+/// `word`, along with their distance, best first, with duplicates
+/// that differ only by case removed.
+fn ranked(word: &str, dict: &dyn Dictionary) -> Vec<(usize, String)> {
+    let target = word.chars().flat_map(|c| c.to_lowercase()).collect::<Vec<_>>();
+    let limit = max_distance(target.len());
+    let first = target.first().cloned();
+    let upper = starts_upper(word);
+
+    let mut candidates = vec![];
+    let mut buf = vec![];
+    dict.each_word(&mut |entry| {
+        buf.clear();
+        buf.extend(entry.chars().flat_map(|c| c.to_lowercase()));
+
+        // the length difference is a lower bound on the distance, and
+        // is far cheaper to compute.
+        let len_diff = if buf.len() > target.len() {
+            buf.len() - target.len()
+        } else {
+            target.len() - buf.len()
+        };
+        if len_diff > limit || buf == target { return }
+
+        let d = osa_distance(&target, &buf);
+        if d <= limit {
+            let other_start = buf.first() != first.as_ref();
+            let shared = Reverse(shared_affixes(&target, &buf));
+            let other_case = starts_upper(entry) != upper;
+            candidates.push((d, other_start, shared, other_case, entry.to_string()))
+        }
+    });
+
+    candidates.sort();
+    // dictionaries can contain the same word with different cases
+    // (e.g. `Bill` and `bill`), only one is useful.
+    let mut ret: Vec<(usize, String)> = vec![];
+    for (d, _, _, _, w) in candidates.into_iter() {
+        if !ret.iter().any(|(_, r)| r.to_lowercase() == w.to_lowercase()) {
+            ret.push((d, w))
+        }
+    }
+    ret
+}
+
+/// The length of the longest common prefix of `a` and `b`, plus
+/// that of the longest common suffix of what is left of them.
+fn shared_affixes(a: &[char], b: &[char]) -> usize {
+    let prefix = a.iter().zip(b).take_while(|&(x, y)| x == y).count();
+    let suffix = a[prefix..].iter().rev().zip(b[prefix..].iter().rev())
+        .take_while(|&(x, y)| x == y).count();
+    prefix + suffix
+}
+
+fn starts_upper(word: &str) -> bool {
+    word.chars().next().is_some_and(|c| c.is_uppercase())
+}
+
+/// Format a misspelled word along with the corrections found for
+/// it, e.g. "dok (did you mean `dock`, `doc`?)".
+pub fn describe(word: &str, suggestions: &[String]) -> String {
+    if suggestions.is_empty() {
+        word.to_string()
+    } else {
+        let quoted = suggestions.iter().map(|s| format!("`{}`", s)).collect::<Vec<_>>();
+        format!("{} (did you mean {}?)", word, quoted.join(", "))
+    }
+}
+
+#[test]
+fn test_suggestions() {
+    assert_eq!(distance("coment", "comment"), 1);
+    assert_eq!(distance("teh", "the"), 1);
+    assert_eq!(distance("Dok", "doc"), 1);
+    assert_eq!(distance("\u{c4}pfel", "\u{e4}pfle"), 1);
+
+    let dict = ["dock", "doc", "dog", "comment", "cement", "Comment"]
+        .iter().map(|s| s.to_string()).collect::<::std::collections::HashSet<_>>();
+    assert_eq!(suggestions("dok", &dict, 2), vec!["dock", "doc"]);
+    assert_eq!(suggestions("coment", &dict, 3), vec!["comment", "cement"]);
+    assert_eq!(suggestions("Coment", &dict, 3), vec!["Comment", "cement"]);
+
+    assert_eq!(correction("Dok", &dict), None);
+    assert_eq!(correction("Commment", &dict), Some("Comment".to_string()));
+    assert_eq!(correction("docck", &dict), Some("dock".to_string()));
+}
@@ -1,5 +1,4 @@
-use std::collections::{BTreeMap, HashSet};
-use std::ascii::AsciiExt;
+use std::collections::BTreeMap;
 use std::cmp::Ordering;
 
 use syntax::{ast, visit};
@@ -10,8 +9,12 @@ use syntax::ast::NodeId;
 
 use rustc::middle::privacy::ExportedItems;
 
-use words;
-use stem;
+use dict::Dictionary;
+use config::{Config, ItemKind};
+use wordmap::WordMap;
+use check::Checker;
+
+pub use check::{Kind, Misspelling, Replacement, group_by_heading};
 
 #[derive(Copy, Debug, Clone)]
 pub struct Position {
@@ -48,84 +51,92 @@ impl Ord for Position {
 /// Keeps track of the reference dictionary and the misspelled words
 /// through a traversal of the whole ast.
 pub struct SpellingVisitor<'a> {
-    /// The reference dictionary.
-    words: &'a HashSet<String>,
+    /// Checks the words.
+    checker: Checker<'a>,
 
     /// The truly exported items.
     exported: &'a ExportedItems,
 
     /// The misspelled words
-    pub misspellings: BTreeMap<Position, Vec<String>>,
+    pub misspellings: BTreeMap<Position, Vec<Misspelling>>,
 }
 
 impl<'a> SpellingVisitor<'a> {
     /// ast::Create a new Spelling Visitor.
-    pub fn new<'b>(words: &'b HashSet<String>,
+    pub fn new<'b>(words: &'b Dictionary,
                    exported: &'b ExportedItems) -> SpellingVisitor<'b> {
+        SpellingVisitor::with_config(words, exported, Config::default())
+    }
+
+    /// Create a new Spelling Visitor, that checks according to
+    /// `config`.
+    pub fn with_config<'b>(words: &'b Dictionary,
+                           exported: &'b ExportedItems,
+                           config: Config) -> SpellingVisitor<'b> {
         SpellingVisitor {
-            words: words,
+            checker: Checker::new(words, config),
             exported: exported,
             misspellings: BTreeMap::new(),
         }
     }
 
-    /// Checks if the given string is a correct "word", without
-    /// splitting it at all. Any word that isn't entirely alphabetic
-    /// is automatically considered a proper word.
-    fn raw_word_is_correct(&mut self, w: &str) -> bool {
-        self.words.contains(w) ||
-            (w.chars().all(|c| c.is_alphabetic()) && {
-                let lower = w.to_ascii_lowercase();
-                self.words.contains(&lower) ||
-                self.stemmed_word_is_correct(&lower)
-            })
+    /// Report any use of the words in `forbidden`, whether or not
+    /// they are spelled correctly.
+    pub fn set_forbidden(&mut self, forbidden: WordMap) {
+        self.checker.set_forbidden(forbidden)
     }
 
-    fn stemmed_word_is_correct(&self, w: &str) -> bool {
-        stem::get(w).ok().map_or(false, |s| self.words.contains(&s))
+    /// Report the misspellings in `known_misspellings` (see
+    /// `Checker::set_known_misspellings`).
+    pub fn set_known_misspellings(&mut self, known_misspellings: WordMap) {
+        self.checker.set_known_misspellings(known_misspellings)
     }
 
-    /// Check a word for correctness, including splitting `foo_bar`
-    /// and `FooBar` into `foo` & `bar` and `Foo` & `Bar`
-    /// respectively. This inserts any incorrect word(s) into the
-    /// misspelling map.
-    fn check_subwords(&mut self, w: &str, pos: Position) {
-        for w in words::subwords(w) {
-            if !self.raw_word_is_correct(w) {
-                let w = w.to_string();
-                self.misspellings.entry(pos).or_insert(vec![]).push(w);
-            }
-        }
+    /// Insert `m` into the misspelling map, at its exact position if
+    /// it is known, or at `pos` otherwise.
+    fn insert(&mut self, m: Misspelling, pos: Position) {
+        let word_pos = match m.span {
+            Some(sp) => Position::new(Span { lo: BytePos(sp.lo as u32),
+                                             hi: BytePos(sp.hi as u32),
+                                             ..pos.span }, pos.id),
+            None => pos
+        };
+        self.misspellings.entry(word_pos).or_insert(vec![]).push(m);
     }
 
     /// Check a single ident for misspellings; possibly separating it
-    /// into subwords.
-    fn check_ident(&mut self, ident: ast::Ident, pos: Position) {
+    /// into subwords. `kind` is the sort of item it names, if it is
+    /// one that can be configured.
+    fn check_ident(&mut self, ident: ast::Ident, pos: Position, kind: Option<ItemKind>) {
         // spooky action at a distance; extracts the string
         // representation from TLS.
         let word = token::get_ident(ident);
-        // secret rust internals, e.g. __std_macros
-        if word.starts_with("__") { return }
-
-        // the ident itself is correct, so shortcircuit to avoid doing
-        // any of the submatching done below.
-        if self.raw_word_is_correct(&word) {
-            return
+        for m in self.checker.check_ident(&word, kind) {
+            self.insert(m, pos)
         }
-
-        self.check_subwords(&word, pos);
     }
 
     /// Check the #[doc="..."] (and the commment forms) attributes for
     /// spelling.
     fn check_doc_attrs(&mut self, attrs: &[ast::Attribute], id: NodeId) {
-        for attr in attrs.iter() {
-            if attr.check_name("doc") {
-                match attr.value_str() {
-                    Some(s) => self.check_subwords(&s, Position::new(attr.span, id)),
-                    None => {}
-                }
-            }
+        let docs = attrs.iter()
+            .filter(|attr| attr.check_name("doc"))
+            .filter_map(|attr| attr.value_str().map(|s| (attr, s)))
+            .collect::<Vec<_>>();
+        let texts = docs.iter().map(|&(attr, ref s)| {
+            // the text of a sugared doc comment is the source text
+            // verbatim, so positions in the string correspond
+            // directly to positions in the file (`#[doc = "..."]` may
+            // have escapes).
+            let BytePos(lo) = attr.span.lo;
+            let BytePos(hi) = attr.span.hi;
+            let exact = attr.node.is_sugared_doc &&
+                (hi - lo) as usize == s.len();
+            (&**s, if exact { Some(lo as usize) } else { None })
+        }).collect::<Vec<_>>();
+
+        for (i, m) in self.checker.check_docs(&texts) {
+            self.insert(m, Position::new(docs[i].0.span, id))
         }
     }
 
@@ -157,7 +168,18 @@ impl<'a, 'v> visit::Visitor<'v> for SpellingVisitor<'a> {
 
         // checking names in impl headers is pointless: they're declared elsewhere.
         if is_exported && !is_impl {
-            self.check_ident(item.ident, Position::new(item.span, item.id));
+            let kind = match item.node {
+                ast::ItemStatic(..) => Some(ItemKind::Static),
+                ast::ItemConst(..) => Some(ItemKind::Const),
+                ast::ItemFn(..) => Some(ItemKind::Fn),
+                ast::ItemMod(..) | ast::ItemForeignMod(..) => Some(ItemKind::Mod),
+                ast::ItemTy(..) => Some(ItemKind::Type),
+                ast::ItemEnum(..) => Some(ItemKind::Enum),
+                ast::ItemStruct(..) => Some(ItemKind::Struct),
+                ast::ItemTrait(..) => Some(ItemKind::Trait),
+                _ => None
+            };
+            self.check_ident(item.ident, Position::new(item.span, item.id), kind);
         }
         if is_exported {
             self.check_doc_attrs(&item.attrs, item.id);
@@ -170,7 +192,8 @@ impl<'a, 'v> visit::Visitor<'v> for SpellingVisitor<'a> {
             ast::ItemEnum(ref ed, _) => {
                 for var in ed.variants.iter() {
                     if self.exported.contains(&var.node.id) {
-                        self.check_ident(var.node.name, Position::new(var.span, var.node.id));
+                        self.check_ident(var.node.name, Position::new(var.span, var.node.id),
+                                         Some(ItemKind::Variant));
                         self.check_doc_attrs(&var.node.attrs, var.node.id);
                     }
                 }
@@ -185,7 +208,8 @@ impl<'a, 'v> visit::Visitor<'v> for SpellingVisitor<'a> {
                     self.check_doc_attrs(&item.attrs, item.id);
                     if !is_trait {
                         // name comes from the trait
-                        self.check_ident(item.ident, Position::new(item.span, item.id));
+                        self.check_ident(item.ident, Position::new(item.span, item.id),
+                                         Some(ItemKind::Method));
                     }
                 }
             }
@@ -198,7 +222,8 @@ impl<'a, 'v> visit::Visitor<'v> for SpellingVisitor<'a> {
 
     fn visit_trait_item(&mut self, trait_item: &ast::TraitItem) {
         self.check_doc_attrs(&trait_item.attrs, trait_item.id);
-        self.check_ident(trait_item.ident, Position::new(trait_item.span, trait_item.id));
+        self.check_ident(trait_item.ident, Position::new(trait_item.span, trait_item.id),
+                         Some(ItemKind::Method));
     }
 
     fn visit_struct_def(&mut self,
@@ -215,7 +240,8 @@ impl<'a, 'v> visit::Visitor<'v> for SpellingVisitor<'a> {
                 match vis {
                     ast::Public => {
                         self.check_ident(ident,
-                                         Position::new(struct_field.span, struct_field.node.id));
+                                         Position::new(struct_field.span, struct_field.node.id),
+                                         Some(ItemKind::Field));
                         self.check_doc_attrs(&struct_field.node.attrs,
                                              struct_field.node.id);
                     }
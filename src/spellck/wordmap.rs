@@ -0,0 +1,118 @@
+//! Lists of words that map to their replacements, like the deny list
+//! of forbidden words and the list of known misspellings.
+//!
+//! The format is one entry per line, either a word alone or a word
+//! and what to use instead, separated by `->`. A deny list of
+//! forbidden words might be:
+//!
+//! ```text
+//! # lines starting with `#` are comments.
+//! whitelist->allowlist
+//! codename
+//! ```
+//!
+//! and a list of known misspellings:
+//!
+//! ```text
+//! recieve->receive
+//! seperate->separate
+//! ```
+//!
+//! This is the format of codespell's dictionaries. A replacement can
+//! list several alternatives separated by commas, in which case none
+//! of them is applied automatically.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io;
+use std::io::prelude::*;
+use std::path::Path;
+
+use dict;
+use suggest;
+
+/// A map from words to the replacements for them, if any. Words are
+/// matched ignoring case.
+#[derive(Clone, Debug, Default)]
+pub struct WordMap {
+    map: HashMap<String, Option<String>>,
+}
+
+impl WordMap {
+    /// Create an empty map.
+    pub fn new() -> WordMap {
+        WordMap::default()
+    }
+
+    /// Add the entries of the file at `path` to the map.
+    pub fn load_into<P: AsRef<Path>>(&mut self, path: P) -> io::Result<()> {
+        let mut s = String::new();
+        File::open(path).and_then(|mut f| f.read_to_string(&mut s))?;
+        self.parse(&s);
+        Ok(())
+    }
+
+    /// Add the entries in `s`, which is in the format described
+    /// above, to the map.
+    pub fn parse(&mut self, s: &str) {
+        for line in s.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') { continue }
+
+            let mut parts = line.splitn(2, "->");
+            let word = parts.next().unwrap().trim();
+            let replacement = parts.next().and_then(|r| {
+                let r = r.trim().trim_end_matches(',').trim();
+                if r.is_empty() { None } else { Some(r.to_string()) }
+            });
+            self.map.insert(key(word), replacement);
+        }
+    }
+
+    /// Whether there are no entries in the map.
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Look up `word`, giving `None` if it isn't in the map, or the
+    /// replacement for it if it is, capitalised to match `word`.
+    pub fn get(&self, word: &str) -> Option<Option<String>> {
+        self.map.get(&key(word)).map(|r| {
+            r.as_ref().map(|r| suggest::match_case(word, r))
+        })
+    }
+}
+
+fn key(word: &str) -> String {
+    dict::normalize(&word.to_lowercase())
+}
+
+/// Whether `replacement` is a single replacement that can be applied
+/// mechanically, rather than a list of alternatives.
+pub fn is_single(replacement: &str) -> bool {
+    !replacement.contains(',')
+}
+
+/// The alternatives listed in `replacement`.
+pub fn alternatives(replacement: &str) -> Vec<&str> {
+    replacement.split(',').map(|r| r.trim()).filter(|r| !r.is_empty()).collect()
+}
+
+#[test]
+fn test_word_map() {
+    let mut map = WordMap::new();
+    map.parse("# comment\n\
+               whitelist -> allowlist\n\
+               recieve->receive\n\
+               codename\n\
+               \n\
+               teh->the, ten, tea,\n");
+
+    assert_eq!(map.get("whitelist"), Some(Some("allowlist".to_string())));
+    assert_eq!(map.get("Recieve"), Some(Some("Receive".to_string())));
+    assert_eq!(map.get("CODENAME"), Some(None));
+    assert_eq!(map.get("teh"), Some(Some("the, ten, tea".to_string())));
+    assert_eq!(map.get("receive"), None);
+    assert!(!is_single("the, ten, tea"));
+    assert_eq!(alternatives("the, ten, tea"), vec!["the", "ten", "tea"]);
+}
@@ -2,35 +2,212 @@
 
 use std::str;
 
+use unicode_segmentation::UnicodeSegmentation;
+
+/// English contractions that aren't a word with a suffix like `'s` or
+/// `'ll` added.
+static CONTRACTIONS: &[&str] = &[
+    "ain't", "aren't", "can't", "couldn't", "daren't", "didn't", "doesn't", "don't",
+    "hadn't", "hasn't", "haven't", "isn't", "mightn't", "mustn't", "needn't", "o'clock",
+    "oughtn't", "shan't", "shouldn't", "wasn't", "weren't", "won't", "wouldn't", "y'all",
+];
+
 /// Struct for the `subwords` iterator.
 pub struct SubwordIter<'a> {
+    inner: SubwordIndices<'a>,
+}
+
+/// Struct for the `subword_indices` iterator.
+pub struct SubwordIndices<'a> {
     s: &'a str,
     iter: str::CharIndices<'a>,
     word_start: Option<usize>,
+    /// The last character seen.
+    prev: char,
+    /// Whether digits following a word are part of it.
+    digits: bool,
 }
 
 /// Iterate over the "subwords" of a string, e.g. `Foobar` -> `Foo`,
-/// `Bar`; `foo_bar` -> `foo`, `bar`; `AB Cd123e` -> `A`, `B`, `Cd`,
-/// `e`.
+/// `Bar`; `foo_bar` -> `foo`, `bar`; `AB Cd123e` -> `AB`, `Cd`,
+/// `e`. A run of capitals is kept together as an acronym, so
+/// `HTTPServer` -> `HTTP`, `Server`, and `URLs` is a single word.
 pub fn subwords<'a>(s: &'a str) -> SubwordIter<'a> {
-    SubwordIter {
-        s: s,
+    SubwordIter { inner: subword_indices(s) }
+}
+
+/// Iterate over the "subwords" of a string (as for `subwords`),
+/// along with the byte offset at which each starts.
+pub fn subword_indices<'a>(s: &'a str) -> SubwordIndices<'a> {
+    SubwordIndices {
+        s,
         iter: s.char_indices(),
-        word_start: None
+        word_start: None,
+        prev: ' ',
+        digits: false,
+    }
+}
+
+/// Iterate over the "subwords" of a string along with their offsets,
+/// as for `subword_indices`, except that digits following a word are
+/// kept as part of it, so `utf8`, `x86_64` and `Sha256Hash` give
+/// `utf8`; `x86`; and `Sha256`, `Hash`.
+pub fn token_indices<'a>(s: &'a str) -> SubwordIndices<'a> {
+    SubwordIndices { digits: true, ..subword_indices(s) }
+}
+
+/// The acronym that `word` is, if it is one: a run of at least two
+/// capitals, optionally pluralised with a lowercase `s` (`IO`,
+/// `URLs`), which gives the acronym without the `s`.
+pub fn acronym(word: &str) -> Option<&str> {
+    let stem = word.strip_suffix('s').unwrap_or(word);
+    if stem.chars().count() >= 2 && stem.chars().all(|c| c.is_uppercase()) {
+        Some(stem)
+    } else {
+        None
+    }
+}
+
+/// The words of some prose, along with the byte offset at which each
+/// starts. These are found with the Unicode word boundary rules (UAX
+/// #29), so contractions and possessives like `don't` and `crate's`
+/// are single words, and then hyphenated words (`well-known`) are
+/// joined back together.
+pub fn prose_word_indices(s: &str) -> Vec<(usize, &str)> {
+    let mut ret: Vec<(usize, &str)> = vec![];
+    // whether the previous segment was a hyphen directly after the
+    // last word.
+    let mut hyphen = false;
+    for (offset, seg) in s.split_word_bound_indices() {
+        if seg.chars().any(|c| c.is_alphanumeric()) {
+            match ret.last_mut() {
+                Some(last) if hyphen => {
+                    *last = (last.0, &s[last.0..offset + seg.len()]);
+                }
+                _ => ret.push((offset, seg))
+            }
+            hyphen = false;
+        } else {
+            hyphen = seg == "-" && ret.last().is_some_and(|&(o, w)| o + w.len() == offset);
+        }
+    }
+    ret
+}
+
+fn is_apostrophe(c: char) -> bool {
+    c == '\'' || c == '\u{2019}'
+}
+
+/// Whether `word` is one of the English contractions that can't be
+/// taken apart, like `won't`.
+pub fn is_contraction(word: &str) -> bool {
+    let word = word.to_lowercase().replace('\u{2019}', "'");
+    CONTRACTIONS.iter().any(|&c| c == word)
+}
+
+/// The word underneath a possessive or a contraction: `crate's` ->
+/// `crate`, `crates'` -> `crates`, `they'll` -> `they`, etc.
+pub fn strip_clitic(word: &str) -> Option<&str> {
+    let i = match word.rfind(is_apostrophe) {
+        Some(i) if i > 0 => i,
+        _ => return None
+    };
+    let base = &word[..i];
+    let suffix = word[i..].chars().skip(1).collect::<String>().to_lowercase();
+    match &*suffix {
+        "" if base.ends_with('s') || base.ends_with('S') => Some(base),
+        "s" | "ll" | "re" | "ve" | "d" | "m" => Some(base),
+        _ => None
+    }
+}
+
+/// Split `word` into the fewest pieces for which `is_word` holds,
+/// each at least two characters long, e.g. `readfile` -> `read`,
+/// `file`. Returns `None` unless there is such a split into at least
+/// two pieces.
+pub fn segment<F: FnMut(&str) -> bool>(word: &str, mut is_word: F) -> Option<Vec<&str>> {
+    let bounds = word.char_indices().map(|(i, _)| i).chain(Some(word.len())).collect::<Vec<_>>();
+    let n = bounds.len() - 1;
+
+    // `best[i]` is the fewest pieces that the first `i` characters
+    // can be split into, along with where the last of them starts.
+    let mut best: Vec<Option<(usize, usize)>> = vec![None; n + 1];
+    best[0] = Some((0, 0));
+    for end in 2..n + 1 {
+        for start in 0..end - 1 {
+            if let Some((count, _)) = best[start] {
+                if best[end].is_none_or(|(c, _)| count + 1 < c) &&
+                    is_word(&word[bounds[start]..bounds[end]]) {
+                    best[end] = Some((count + 1, start));
+                }
+            }
+        }
+    }
+
+    match best[n] {
+        Some((count, _)) if count >= 2 => {
+            let mut pieces = vec![];
+            let mut end = n;
+            while end > 0 {
+                let start = best[end].unwrap().1;
+                pieces.push(&word[bounds[start]..bounds[end]]);
+                end = start;
+            }
+            pieces.reverse();
+            Some(pieces)
+        }
+        _ => None
     }
 }
 
 impl<'a> Iterator for SubwordIter<'a> {
     type Item = &'a str;
     fn next(&mut self) -> Option<&'a str> {
-        let mut word_start = self.word_start;
-        for (offset, c) in &mut self.iter {
+        self.inner.next().map(|(_, w)| w)
+    }
+}
+
+impl<'a> SubwordIndices<'a> {
+    /// Whether `c`, at `offset` in the middle of a word, starts a new
+    /// one.
+    fn starts_word(&self, offset: usize, c: char) -> bool {
+        if self.prev.is_numeric() {
+            // utf8Bytes, x86a
+            return c.is_alphabetic()
+        }
+        if !c.is_uppercase() {
+            return false
+        }
+        if !self.prev.is_uppercase() {
+            // fooBar
+            return true
+        }
+        // the last capital in a run starts a new word if lowercase
+        // letters follow it, `HTTPServer`, other than the `s` of a
+        // plural acronym, `URLs`.
+        let mut rest = self.s[offset + c.len_utf8()..].chars();
+        match (rest.next(), rest.next()) {
+            (Some('s'), after) => after.is_some_and(|a| a.is_lowercase()),
+            (Some(next), _) => next.is_lowercase(),
+            (None, _) => false
+        }
+    }
+}
+
+impl<'a> Iterator for SubwordIndices<'a> {
+    type Item = (usize, &'a str);
+    fn next(&mut self) -> Option<(usize, &'a str)> {
+        while let Some((offset, c)) = self.iter.next() {
             // skip leading non-alphabetic characters
             let alpha = c.is_alphabetic();
-            match word_start {
-                None if alpha => word_start = Some(offset),
-                None => {}
-                Some(ws) if !alpha || c.is_uppercase() => {
+            let continues = alpha || (self.digits && c.is_numeric());
+            let ret = match self.word_start {
+                None if alpha => {
+                    self.word_start = Some(offset);
+                    None
+                }
+                None => None,
+                Some(ws) if !continues || self.starts_word(offset, c) => {
                     self.word_start = if alpha {
                         // need to reuse this character for the next word
                         Some(offset)
@@ -38,12 +215,16 @@ impl<'a> Iterator for SubwordIter<'a> {
                         None
                     };
 
-                    return Some(&self.s[ws..offset])
+                    Some((ws, &self.s[ws..offset]))
                 }
-                Some(_) => {}
+                Some(_) => None
+            };
+            self.prev = c;
+            if ret.is_some() {
+                return ret
             }
         }
-        word_start.map(|ws| { self.word_start = None; &self.s[ws..] })
+        self.word_start.take().map(|ws| (ws, &self.s[ws..]))
     }
 }
 
@@ -52,5 +233,59 @@ fn test_words() {
     let s = "Foo_barBazÄåöAB123C";
 
     assert_eq!(subwords(s).collect::<Vec<_>>(),
-               vec!("Foo", "bar", "Baz", "Äåö", "A", "B", "C"));
+               vec!("Foo", "bar", "Baz", "Äåö", "AB", "C"));
+
+    assert_eq!(subwords("HTTPServer IOError URLs getHTTPSUrl").collect::<Vec<_>>(),
+               vec!("HTTP", "Server", "IO", "Error", "URLs", "get", "HTTPS", "Url"));
+
+    assert_eq!(acronym("HTTP"), Some("HTTP"));
+    assert_eq!(acronym("URLs"), Some("URL"));
+    assert_eq!(acronym("Url"), None);
+    assert_eq!(acronym("I"), None);
+}
+
+#[test]
+fn test_segment() {
+    let dict = ["read", "file", "is", "empty", "a", "b", "ab"];
+    let is_word = |w: &str| dict.contains(&w);
+
+    assert_eq!(segment("readfile", is_word), Some(vec!["read", "file"]));
+    assert_eq!(segment("isempty", is_word), Some(vec!["is", "empty"]));
+    assert_eq!(segment("read", is_word), None);
+    assert_eq!(segment("readfiel", is_word), None);
+    assert_eq!(segment("abab", is_word), Some(vec!["ab", "ab"]));
+    assert_eq!(segment("aread", is_word), None);
+}
+
+#[test]
+fn test_tokens() {
+    let s = "utf8 x86_64 Sha256Hash 2D";
+
+    assert_eq!(token_indices(s).collect::<Vec<_>>(),
+               vec!((0, "utf8"), (5, "x86"), (12, "Sha256"), (18, "Hash"), (24, "D")));
+}
+
+#[test]
+fn test_prose_words() {
+    let s = "Don't split the crate's well-known words\u{2019}s - or x86_64.";
+
+    assert_eq!(prose_word_indices(s),
+               vec!((0, "Don't"), (6, "split"), (12, "the"), (16, "crate's"),
+                    (24, "well-known"), (35, "words\u{2019}s"), (47, "or"), (50, "x86_64")));
+
+    assert!(is_contraction("Won\u{2019}t"));
+    assert!(!is_contraction("wont"));
+    assert_eq!(strip_clitic("crate's"), Some("crate"));
+    assert_eq!(strip_clitic("crates'"), Some("crates"));
+    assert_eq!(strip_clitic("they\u{2019}ll"), Some("they"));
+    assert_eq!(strip_clitic("don't"), None);
+    assert_eq!(strip_clitic("'s"), None);
+}
+
+#[test]
+fn test_word_indices() {
+    let s = "/// Bad dok_Coment";
+
+    assert_eq!(subword_indices(s).collect::<Vec<_>>(),
+               vec!((4, "Bad"), (8, "dok"), (12, "Coment")));
 }